@@ -0,0 +1,251 @@
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::{Assigned, ConstraintSystem, Error},
+};
+
+use crate::{
+    error::LookupError, logup::assignments::LogupGate, range::assignments::to_limbs, AssignedValue,
+    RegionCtx, Witness,
+};
+
+use super::config::BitwiseConfig;
+
+/// Bitwise operation, its table is tagged with its index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseOp {
+    Xor,
+    And,
+    Or,
+    /// Second operand is zero
+    Not,
+}
+
+impl BitwiseOp {
+    const ALL: [BitwiseOp; 4] = [
+        BitwiseOp::Xor,
+        BitwiseOp::And,
+        BitwiseOp::Or,
+        BitwiseOp::Not,
+    ];
+
+    fn tag(self) -> usize {
+        self as usize
+    }
+
+    /// Applies the operation to chunks of `chunk_bits`
+    pub fn apply(self, a: u64, b: u64, chunk_bits: usize) -> u64 {
+        match self {
+            BitwiseOp::Xor => a ^ b,
+            BitwiseOp::And => a & b,
+            BitwiseOp::Or => a | b,
+            BitwiseOp::Not => !a & ((1 << chunk_bits) - 1),
+        }
+    }
+
+    /// Rows `(a, b, a op b)` over all chunks
+    fn table<F: PrimeField>(self, chunk_bits: usize) -> Vec<Vec<F>> {
+        let size = 1u64 << chunk_bits;
+        let b_range = match self {
+            BitwiseOp::Not => 0..1,
+            _ => 0..size,
+        };
+        (0..size)
+            .flat_map(|a| b_range.clone().map(move |b| (a, b)))
+            .map(|(a, b)| {
+                [a, b, self.apply(a, b, chunk_bits)]
+                    .into_iter()
+                    .map(F::from)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Running sums `z_i = chunk_i + 2^chunk_bits * z_{i+1}` that end with `z_n = 0`
+fn running_sum<F: PrimeField>(chunks: &[Value<F>], chunk_bits: usize) -> Vec<Value<F>> {
+    let base = Value::known(F::from(1u64 << chunk_bits));
+    let mut z = vec![Value::known(F::ZERO); chunks.len() + 1];
+    for (i, chunk) in chunks.iter().enumerate().rev() {
+        z[i] = *chunk + base * z[i + 1];
+    }
+    z
+}
+
+/// Bitwise operations on words that are split into chunks of `chunk_bits`. Chunks of operands
+/// and results are looked up in `(a, b, a op b)` tables that share one tagged logup gate so
+/// operands are also range checked to their bit size.
+#[derive(Clone, Debug)]
+pub struct BitwiseChip<F: PrimeField> {
+    cfg: BitwiseConfig<F>,
+    gate: LogupGate<F>,
+    lookups: Vec<(BitwiseOp, Vec<AssignedValue<F>>)>,
+}
+
+impl<F: PrimeField> BitwiseChip<F> {
+    /// Configures the chip with tables of `2^(2 * chunk_bits)` rows where `width` chunks are
+    /// looked up per row
+    pub fn configure(meta: &mut ConstraintSystem<F>, chunk_bits: usize, width: usize) -> Self {
+        let cfg = BitwiseConfig::configure(meta, chunk_bits);
+        let tables = BitwiseOp::ALL
+            .iter()
+            .map(|op| op.table(chunk_bits))
+            .collect();
+        let gate = LogupGate::configure_tagged(meta, tables, width);
+        Self {
+            cfg,
+            gate,
+            lookups: Vec::new(),
+        }
+    }
+
+    pub fn chunk_bits(&self) -> usize {
+        self.cfg.chunk_bits
+    }
+
+    /// Returns `a op b` for words of `bits` bits, `b` is ignored for `Not`
+    fn apply(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        op: BitwiseOp,
+        a: &AssignedValue<F>,
+        b: Option<&AssignedValue<F>>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        let chunk_bits = self.chunk_bits();
+        let n = bits / chunk_bits;
+        assert!(n > 0 && n * chunk_bits == bits && bits < F::NUM_BITS as usize);
+
+        let chunks = |value: Value<F>| {
+            value
+                .map(|value| to_limbs(&value, chunk_bits, n))
+                .transpose_vec(n)
+        };
+        let a_chunks = chunks(a.value().map(|a| a.evaluate()));
+        let b_chunks = match b {
+            Some(b) => chunks(b.value().map(|b| b.evaluate())),
+            None => vec![Value::known(0); n],
+        };
+        let r_chunks = a_chunks
+            .iter()
+            .zip(b_chunks.iter())
+            .map(|(a, b)| a.zip(*b).map(|(a, b)| op.apply(a, b, chunk_bits)))
+            .collect::<Vec<_>>();
+
+        let columns = [a_chunks, b_chunks, r_chunks].map(|chunks| {
+            let chunks = chunks
+                .into_iter()
+                .map(|chunk| chunk.map(F::from))
+                .collect::<Vec<_>>();
+            let z = running_sum(&chunks, chunk_bits);
+            (chunks, z)
+        });
+
+        let (chunks, result) = ly.assign_region(
+            || "bitwise",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+                let mut assigned = Vec::with_capacity(n);
+                let mut result = None;
+
+                for i in 0..n {
+                    ctx.enable(self.cfg.s_sum)?;
+                    let mut row = Vec::with_capacity(3);
+                    for (j, (chunks, z)) in columns.iter().enumerate() {
+                        let z = match (i, j, b) {
+                            (0, 0, _) => ctx.copy(self.cfg.z[j], a)?,
+                            (0, 1, Some(b)) => ctx.copy(self.cfg.z[j], b)?,
+                            _ => ctx.advice(self.cfg.z[j], z[i].map(Assigned::from))?,
+                        };
+                        if i == 0 && j == 2 {
+                            result = Some(z);
+                        }
+                        row.push(ctx.advice(self.cfg.chunk[j], chunks[i].map(Assigned::from))?);
+                    }
+                    assigned.push(row);
+                    ctx.next();
+                }
+
+                ctx.enable(self.cfg.s_last)?;
+                for z in self.cfg.z.iter() {
+                    ctx.advice(*z, Value::known(F::ZERO).into())?;
+                }
+
+                Ok((assigned, result.expect("at least one chunk")))
+            },
+        )?;
+
+        self.lookups
+            .extend(chunks.into_iter().map(|chunks| (op, chunks)));
+        Ok(result)
+    }
+
+    /// Returns `a ^ b` for words of `bits` bits, `bits` must be a multiple of `chunk_bits`
+    pub fn xor(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.apply(ly, BitwiseOp::Xor, a, Some(b), bits)
+    }
+
+    /// Returns `a & b` for words of `bits` bits, `bits` must be a multiple of `chunk_bits`
+    pub fn and(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.apply(ly, BitwiseOp::And, a, Some(b), bits)
+    }
+
+    /// Returns `a | b` for words of `bits` bits, `bits` must be a multiple of `chunk_bits`
+    pub fn or(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.apply(ly, BitwiseOp::Or, a, Some(b), bits)
+    }
+
+    /// Returns `!a` for words of `bits` bits, `bits` must be a multiple of `chunk_bits`
+    pub fn not(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.apply(ly, BitwiseOp::Not, a, None, bits)
+    }
+
+    /// Lays out lookups of all chunks in one lookup gate
+    pub fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), LookupError<F>> {
+        let mut gate = self.gate.clone();
+        let width = gate.width();
+        for op in BitwiseOp::ALL {
+            let tuples = self
+                .lookups
+                .iter()
+                .filter(|(lookup, _)| *lookup == op)
+                .map(|(_, chunks)| chunks.iter().cloned().map(Witness::Assigned).collect())
+                .collect::<Vec<Vec<_>>>();
+            for tuples in tuples.chunks(width) {
+                // last row is filled with tuples of the same row
+                let row = tuples
+                    .iter()
+                    .cycle()
+                    .take(width)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                gate.lookup_tagged_witness(op.tag(), &row, None);
+            }
+        }
+        gate.layout_tuple(ly)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use ff::PrimeField;
+use halo2::{
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct BitwiseConfig<F: PrimeField> {
+    /// Running sums of operands and the result
+    pub(crate) z: [Column<Advice>; 3],
+    /// Chunks of operands and the result
+    pub(crate) chunk: [Column<Advice>; 3],
+    pub(crate) chunk_bits: usize,
+
+    pub(crate) s_sum: Selector,
+    pub(crate) s_last: Selector,
+
+    pub(crate) marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BitwiseConfig<F> {
+    /// Decomposes both operands and the result into little endian chunks of `chunk_bits` with
+    /// running sums `z_i = chunk_i + 2^chunk_bits * z_{i+1}` that end with `z_n = 0`
+    pub fn configure(meta: &mut ConstraintSystem<F>, chunk_bits: usize) -> Self {
+        assert!(chunk_bits > 0 && chunk_bits < 32);
+        let z = [(); 3].map(|_| meta.advice_column());
+        let chunk = [(); 3].map(|_| meta.advice_column());
+        // operands are copied in, chunks are copied into the lookup gate and the result is
+        // copied out
+        z.iter()
+            .chain(chunk.iter())
+            .for_each(|column| meta.enable_equality(*column));
+
+        let s_sum = meta.selector();
+        let s_last = meta.selector();
+
+        meta.create_gate("running sums", |meta| {
+            let base = Expression::Constant(F::from(1u64 << chunk_bits));
+            let identities = z
+                .iter()
+                .zip(chunk.iter())
+                .map(|(z, chunk)| {
+                    let z_next = meta.query_advice(*z, Rotation(1));
+                    let z = meta.query_advice(*z, Rotation(0));
+                    let chunk = meta.query_advice(*chunk, Rotation(0));
+                    z - chunk - base.clone() * z_next
+                })
+                .collect::<Vec<_>>();
+            let selector = meta.query_selector(s_sum);
+            Constraints::with_selector(selector, identities)
+        });
+
+        meta.create_gate("zero sums", |meta| {
+            let identities = z
+                .iter()
+                .map(|z| meta.query_advice(*z, Rotation(0)))
+                .collect::<Vec<_>>();
+            let selector = meta.query_selector(s_last);
+            Constraints::with_selector(selector, identities)
+        });
+
+        Self {
+            z,
+            chunk,
+            chunk_bits,
+
+            s_sum,
+            s_last,
+
+            marker: PhantomData,
+        }
+    }
+}
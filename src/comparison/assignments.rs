@@ -0,0 +1,132 @@
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::{Assigned, ConstraintSystem, Error},
+};
+
+use crate::{
+    error::LookupError, index::le_bytes, range::assignments::RangeChip, AssignedValue, LookupGate,
+    RegionCtx,
+};
+
+use super::config::ComparisonConfig;
+
+/// Compares the canonical representations of `a` and `b` as integers
+fn is_less_than<F: PrimeField>(a: &F, b: &F) -> bool {
+    le_bytes(a).iter().rev().lt(le_bytes(b).iter().rev())
+}
+
+/// Compares values of at most `n` bits. Remainders of comparisons are range checked with a
+/// single range chip so all lookups of the chip end up in one lookup gate.
+#[derive(Clone, Debug)]
+pub struct ComparisonChip<F: PrimeField, Gate: LookupGate<F>> {
+    cfg: ComparisonConfig<F>,
+    range: RangeChip<F, Gate>,
+}
+
+impl<F: PrimeField, Gate: LookupGate<F>> ComparisonChip<F, Gate> {
+    /// Configures the chip with the range table `0..2^limb_bits` where `width` limbs are
+    /// looked up per row
+    pub fn configure(meta: &mut ConstraintSystem<F>, limb_bits: usize, width: usize) -> Self {
+        let cfg = ComparisonConfig::configure(meta);
+        let range = RangeChip::configure(meta, limb_bits, width);
+        Self { cfg, range }
+    }
+
+    /// Range chip of the comparisons, can be used to range check compared values with the same
+    /// lookup gate
+    pub fn range(&mut self) -> &mut RangeChip<F, Gate> {
+        &mut self.range
+    }
+
+    /// Returns `(a < b, a >= b)` as booleans. `a` and `b` must be already constrained to `bits`.
+    fn compare(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        assert!(bits > 0 && bits + 1 < F::NUM_BITS as usize);
+        let bound = F::from(2u64).pow_vartime([bits as u64]);
+
+        let a_value = a.value().map(|a| a.evaluate());
+        let b_value = b.value().map(|b| b.evaluate());
+        let lt = a_value.zip(b_value).map(|(a, b)| is_less_than(&a, &b));
+        // r = a - b + 2^n * lt
+        let r = a_value.zip(b_value).zip(lt).map(|((a, b), lt)| match lt {
+            true => a - b + bound,
+            false => a - b,
+        });
+        let lt: Value<F> = lt.map(|lt| F::from(lt as u64));
+        let ge = lt.map(|lt| F::ONE - lt);
+
+        let (r, lt, ge) = ly.assign_region(
+            || "compare",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+                ctx.enable(self.cfg.s_compare)?;
+                ctx.copy(self.cfg.a, a)?;
+                ctx.copy(self.cfg.b, b)?;
+                ctx.fixed(self.cfg.bound, bound)?;
+                let r = ctx.advice(self.cfg.r, r.map(Assigned::from))?;
+                let lt = ctx.advice(self.cfg.lt, lt.map(Assigned::from))?;
+                let ge = ctx.advice(self.cfg.ge, ge.map(Assigned::from))?;
+                Ok((r, lt, ge))
+            },
+        )?;
+        self.range.range_check(ly, &r, bits)?;
+
+        Ok((lt, ge))
+    }
+
+    /// Returns one if `a < b` and zero otherwise. `a` and `b` must be already constrained to
+    /// `bits`, see `range`.
+    pub fn less_than(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.compare(ly, a, b, bits).map(|(lt, _)| lt)
+    }
+
+    /// Returns one if `a >= b` and zero otherwise
+    pub fn greater_or_equal(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.compare(ly, a, b, bits).map(|(_, ge)| ge)
+    }
+
+    /// Returns one if `a > b` and zero otherwise
+    pub fn greater_than(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.less_than(ly, b, a, bits)
+    }
+
+    /// Returns one if `a <= b` and zero otherwise
+    pub fn less_or_equal(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.greater_or_equal(ly, b, a, bits)
+    }
+
+    /// Lays out the range checks of all comparisons in one lookup gate
+    pub fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), LookupError<F>> {
+        self.range.layout(ly)
+    }
+}
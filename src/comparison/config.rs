@@ -0,0 +1,72 @@
+use ff::PrimeField;
+use halo2::{
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct ComparisonConfig<F: PrimeField> {
+    pub(crate) a: Column<Advice>,
+    pub(crate) b: Column<Advice>,
+    pub(crate) r: Column<Advice>,
+    pub(crate) lt: Column<Advice>,
+    pub(crate) ge: Column<Advice>,
+    pub(crate) bound: Column<Fixed>,
+
+    pub(crate) s_compare: Selector,
+
+    pub(crate) marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ComparisonConfig<F> {
+    /// `a - b + 2^n = r + 2^n * ge` where `ge` is boolean and `lt = 1 - ge`. If `a` and `b` are
+    /// less than `2^n` and `r` is range checked to `n` bits then `ge` is one iff `a >= b`.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let r = meta.advice_column();
+        let lt = meta.advice_column();
+        let ge = meta.advice_column();
+        let bound = meta.fixed_column();
+        // compared values are copied in, r is copied into the range check and results are
+        // copied out
+        for column in [a, b, r, lt, ge] {
+            meta.enable_equality(column);
+        }
+
+        let s_compare = meta.selector();
+
+        meta.create_gate("compare", |meta| {
+            let a = meta.query_advice(a, Rotation(0));
+            let b = meta.query_advice(b, Rotation(0));
+            let r = meta.query_advice(r, Rotation(0));
+            let lt = meta.query_advice(lt, Rotation(0));
+            let ge = meta.query_advice(ge, Rotation(0));
+            let bound = meta.query_fixed(bound, Rotation(0));
+            let one = Expression::Constant(F::ONE);
+            let selector = meta.query_selector(s_compare);
+            Constraints::with_selector(
+                selector,
+                [
+                    ge.clone() * (one.clone() - ge.clone()),
+                    lt + ge.clone() - one,
+                    a - b + bound.clone() - r - bound * ge,
+                ],
+            )
+        });
+
+        Self {
+            a,
+            b,
+            r,
+            lt,
+            ge,
+            bound,
+
+            s_compare,
+
+            marker: PhantomData,
+        }
+    }
+}
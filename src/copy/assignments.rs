@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::{Assigned, ConstraintSystem, Error},
+};
+
+use crate::{logup::config::compress, AssignedValue, EqualityGate, RegionCtx, Witness};
+
+use super::config::CopyConfig;
+
+#[derive(Clone, Debug)]
+pub struct CopyGate<F: PrimeField> {
+    cfg: CopyConfig<F>,
+    rows: Vec<Vec<Witness<F>>>,
+    equalities: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl<F: PrimeField> CopyGate<F> {
+    fn position(&self, (row, column): (usize, usize)) -> usize {
+        row * self.width() + column
+    }
+
+    /// Returns `sigma` where each equality class of cells is a cycle
+    fn permutation(&self) -> Vec<usize> {
+        let n = self.rows.len() * self.width();
+
+        // union find over cell positions
+        let mut parent = (0..n).collect::<Vec<_>>();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for (cell_0, cell_1) in self.equalities.iter() {
+            let root_0 = find(&mut parent, self.position(*cell_0));
+            let root_1 = find(&mut parent, self.position(*cell_1));
+            parent[root_0] = root_1;
+        }
+
+        let mut classes: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..n {
+            classes.entry(find(&mut parent, i)).or_default().push(i);
+        }
+
+        let mut sigma = (0..n).collect::<Vec<_>>();
+        for class in classes.values() {
+            for (j, i) in class.iter().enumerate() {
+                sigma[*i] = class[(j + 1) % class.len()];
+            }
+        }
+        sigma
+    }
+}
+
+impl<F: PrimeField> EqualityGate<F> for CopyGate<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, width: usize) -> Self {
+        let w = std::iter::repeat_with(|| meta.advice_column())
+            .take(width)
+            .collect::<Vec<_>>();
+
+        let cfg = CopyConfig::configure(meta, &w);
+
+        Self {
+            cfg,
+            rows: Vec::new(),
+            equalities: Vec::new(),
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.cfg.w.len()
+    }
+
+    fn assign(&mut self, value: &[Witness<F>]) -> usize {
+        assert_eq!(value.len(), self.width());
+        self.rows.push(value.to_vec());
+        self.rows.len() - 1
+    }
+
+    fn equal(&mut self, cell_0: (usize, usize), cell_1: (usize, usize)) {
+        for (row, column) in [cell_0, cell_1] {
+            assert!(row < self.rows.len() && column < self.width());
+        }
+        self.equalities.push((cell_0, cell_1));
+    }
+
+    fn layout(&self, ly: &mut impl Layouter<F>) -> Result<Vec<Vec<AssignedValue<F>>>, Error> {
+        let width = self.width();
+        let sigma = self.permutation();
+
+        let alpha: Value<F> = ly.get_challenge(self.cfg.alpha);
+        let beta: Value<F> = ly.get_challenge(self.cfg.beta);
+
+        // helper = 1 / (alpha - w - beta * position)
+        let helper = |w: Value<F>, position: usize| {
+            let position = Value::known(F::from(position as u64));
+            let w = compress([w, position].into_iter(), beta);
+            (alpha - w).map(|inv| Assigned::Rational(F::ONE, inv))
+        };
+
+        ly.assign_region(
+            || "copy",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+                let mut assigned = Vec::with_capacity(self.rows.len());
+
+                // init acc to zero
+                let mut acc: Value<Assigned<F>> = Value::known(F::ZERO).into();
+                ctx.enable(self.cfg.s_zero)?;
+
+                for (i, w) in self.rows.iter().enumerate() {
+                    ctx.enable(self.cfg.s_acc)?;
+                    ctx.enable(self.cfg.s_w)?;
+                    ctx.advice(self.cfg.acc, acc)?;
+
+                    let mut row = Vec::with_capacity(width);
+                    for (j, w) in w.iter().enumerate() {
+                        let position = i * width + j;
+                        row.push(ctx.witness(self.cfg.w[j], w)?);
+                        ctx.fixed(self.cfg.id[j], F::from(position as u64))?;
+                        ctx.fixed(self.cfg.sigma[j], F::from(sigma[position] as u64))?;
+
+                        let id_helper = helper(w.value(), position);
+                        let sigma_helper = helper(w.value(), sigma[position]);
+                        ctx.advice(self.cfg.id_helper[j], id_helper)?;
+                        ctx.advice(self.cfg.sigma_helper[j], sigma_helper)?;
+                        acc = acc + id_helper - sigma_helper;
+                    }
+                    assigned.push(row);
+                    ctx.next();
+                }
+
+                ctx.advice(self.cfg.acc, acc)?;
+                ctx.enable(self.cfg.s_zero)?;
+
+                Ok(assigned)
+            },
+        )
+    }
+}
@@ -0,0 +1,136 @@
+use ff::PrimeField;
+use halo2::{
+    plonk::{
+        Advice, Challenge, Column, ConstraintSystem, Constraints, Expression, FirstPhase, Fixed,
+        SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::logup::config::compress;
+
+#[derive(Clone, Debug)]
+pub struct CopyConfig<F: PrimeField> {
+    pub(crate) w: Vec<Column<Advice>>,
+    pub(crate) id: Vec<Column<Fixed>>,
+    pub(crate) sigma: Vec<Column<Fixed>>,
+    pub(crate) id_helper: Vec<Column<Advice>>,
+    pub(crate) sigma_helper: Vec<Column<Advice>>,
+    pub(crate) acc: Column<Advice>,
+    pub(crate) alpha: Challenge,
+    pub(crate) beta: Challenge,
+
+    pub(crate) s_zero: Selector,
+    pub(crate) s_acc: Selector,
+
+    pub(crate) s_w: Selector,
+
+    pub(crate) marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> CopyConfig<F> {
+    /// Cells in `w` are tagged with their position `id` and with `sigma` that is the position of
+    /// the next cell in the same equality cycle. Pairs `(w, id)` and `(w, sigma)` are the same
+    /// multiset only if values are equal along each cycle.
+    pub fn configure(meta: &mut ConstraintSystem<F>, w: &[Column<Advice>]) -> Self {
+        assert!(!w.is_empty());
+        // cells can be copied from and to other regions
+        w.iter().for_each(|w| meta.enable_equality(*w));
+        let id = std::iter::repeat_with(|| meta.fixed_column())
+            .take(w.len())
+            .collect::<Vec<_>>();
+        let sigma = std::iter::repeat_with(|| meta.fixed_column())
+            .take(w.len())
+            .collect::<Vec<_>>();
+        let id_helper = std::iter::repeat_with(|| meta.advice_column_in(SecondPhase))
+            .take(w.len())
+            .collect::<Vec<_>>();
+        let sigma_helper = std::iter::repeat_with(|| meta.advice_column_in(SecondPhase))
+            .take(w.len())
+            .collect::<Vec<_>>();
+        let acc = meta.advice_column_in(SecondPhase);
+
+        let alpha = meta.challenge_usable_after(FirstPhase);
+        // pair compression
+        let beta = meta.challenge_usable_after(FirstPhase);
+
+        let s_zero = meta.selector();
+        let s_acc = meta.selector();
+        let s_w = meta.complex_selector();
+
+        // id_helper(X) * (alpha - w(X) - beta * id(X)) = 1
+        // sigma_helper(X) * (alpha - w(X) - beta * sigma(X)) = 1
+        meta.create_gate("copy-helper", |meta| {
+            let alpha = meta.query_challenge(alpha);
+            let beta = meta.query_challenge(beta);
+            let identities = w
+                .iter()
+                .zip(id.iter().zip(sigma.iter()))
+                .zip(id_helper.iter().zip(sigma_helper.iter()))
+                .flat_map(|((w, (id, sigma)), (id_helper, sigma_helper))| {
+                    let w = meta.query_advice(*w, Rotation(0));
+                    let id = meta.query_fixed(*id, Rotation(0));
+                    let sigma = meta.query_fixed(*sigma, Rotation(0));
+                    let id_helper = meta.query_advice(*id_helper, Rotation(0));
+                    let sigma_helper = meta.query_advice(*sigma_helper, Rotation(0));
+                    let id = compress([w.clone(), id].into_iter(), beta.clone());
+                    let sigma = compress([w, sigma].into_iter(), beta.clone());
+                    [
+                        id_helper * (alpha.clone() - id) - Expression::Constant(F::ONE),
+                        sigma_helper * (alpha.clone() - sigma) - Expression::Constant(F::ONE),
+                    ]
+                })
+                .collect::<Vec<_>>();
+
+            let selector = meta.query_selector(s_w);
+            Constraints::with_selector(selector, identities)
+        });
+
+        // sum(id_helper(x) - sigma_helper(x)) == 0
+        meta.create_gate("grand sum", |meta| {
+            let s_w = meta.query_selector(s_w);
+            let id_helper = id_helper
+                .iter()
+                .map(|helper| meta.query_advice(*helper, Rotation(0)))
+                .sum::<Expression<F>>();
+            let sigma_helper = sigma_helper
+                .iter()
+                .map(|helper| meta.query_advice(*helper, Rotation(0)))
+                .sum::<Expression<F>>();
+            let contrib = s_w * (id_helper - sigma_helper);
+
+            let acc_next = meta.query_advice(acc, Rotation(1));
+            let acc = meta.query_advice(acc, Rotation(0));
+            let identity = contrib + acc - acc_next;
+
+            let selector = meta.query_selector(s_acc);
+            Constraints::with_selector(selector, std::iter::once(identity))
+        });
+
+        // zero sum check
+        meta.create_gate("zero acc", |meta| {
+            let acc = meta.query_advice(acc, Rotation(0));
+            let selector = meta.query_selector(s_zero);
+            Constraints::with_selector(selector, std::iter::once(acc))
+        });
+
+        Self {
+            w: w.to_vec(),
+            id,
+            sigma,
+            id_helper,
+            sigma_helper,
+            acc,
+            alpha,
+            beta,
+
+            s_zero,
+            s_acc,
+
+            s_w,
+
+            marker: PhantomData,
+        }
+    }
+}
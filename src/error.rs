@@ -0,0 +1,91 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use halo2::plonk::Error;
+
+/// Identity of the logup argument that fails for the final witness
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Identity {
+    /// `t_helper * (alpha - t) = 1` at `row` of the table, fails if alpha collides with `t`
+    TableHelper { row: usize },
+    /// `w_helper * (alpha - w) = q` at `column`th lookup of `lookup`th row, fails if alpha
+    /// collides with `w`
+    WitnessHelper { lookup: usize, column: usize },
+    /// `sum m_i / (alpha - t_i) = sum q_j / (alpha - w_j)`
+    GrandSum,
+}
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Identity::TableHelper { row } => write!(f, "table helper at row {row}"),
+            Identity::WitnessHelper { lookup, column } => {
+                write!(f, "witness helper of lookup {lookup} column {column}")
+            }
+            Identity::GrandSum => write!(f, "grand sum"),
+        }
+    }
+}
+
+/// Errors of laying out lookup gates. Converts into `halo2::plonk::Error` so that it can be
+/// propagated from `Circuit::synthesize`.
+#[derive(Debug)]
+pub enum LookupError<F> {
+    /// Error returned by halo2 while assigning cells
+    Synthesis(Error),
+    /// `column`th value of the `lookup`th row of lookups is not in the table
+    NotInTable {
+        lookup: usize,
+        column: usize,
+        value: Vec<F>,
+    },
+    /// An identity of the argument does not hold for the final witness
+    Identity(Identity),
+    /// Alpha equals a table or witness value so the helper of the identity has no inverse
+    AlphaCollision(Identity),
+    /// Gate requires `required` rows which exceeds the usable rows of `2^k`
+    NotEnoughRows { required: usize, k: u32 },
+}
+
+impl<F: Debug> Display for LookupError<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::Synthesis(err) => write!(f, "synthesis error: {err}"),
+            LookupError::NotInTable {
+                lookup,
+                column,
+                value,
+            } => write!(
+                f,
+                "lookup {lookup} column {column}: value {value:?} is not in the table"
+            ),
+            LookupError::Identity(identity) => write!(f, "identity does not hold: {identity}"),
+            LookupError::AlphaCollision(identity) => {
+                write!(f, "alpha collides with the value of {identity}")
+            }
+            LookupError::NotEnoughRows { required, k } => {
+                write!(
+                    f,
+                    "{required} rows are required, not enough rows for k = {k}"
+                )
+            }
+        }
+    }
+}
+
+impl<F: Debug> std::error::Error for LookupError<F> {}
+
+impl<F> From<Error> for LookupError<F> {
+    fn from(err: Error) -> Self {
+        LookupError::Synthesis(err)
+    }
+}
+
+impl<F> From<LookupError<F>> for Error {
+    fn from(err: LookupError<F>) -> Self {
+        match err {
+            LookupError::Synthesis(err) => err,
+            LookupError::NotEnoughRows { k, .. } => Error::NotEnoughRowsAvailable { current_k: k },
+            _ => Error::Synthesis,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use ff::PrimeField;
+
+/// Little endian bytes of the canonical representation of `value`
+pub(crate) fn le_bytes<F: PrimeField>(value: &F) -> Vec<u8> {
+    let mut bytes = value.to_repr().as_ref().to_vec();
+    // representation of one tells the byte order
+    if F::ONE.to_repr().as_ref()[0] != 1 {
+        bytes.reverse();
+    }
+    bytes
+}
+
+/// Returns `value` as an integer if it is smaller than `2^64`
+fn small_value<F: PrimeField>(value: &F) -> Option<u64> {
+    let bytes = le_bytes(value);
+    let (low, high) = bytes.split_at(8);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(u64::from_le_bytes(low.try_into().unwrap()))
+}
+
+/// Maps a table row to the index of its first occurrence in the table
+#[derive(Clone, Debug)]
+pub(crate) enum TableIndex {
+    /// Table is `0..n` so a value is its own index
+    Range(usize),
+    /// Rows are keyed by the representation of their values
+    Hash(HashMap<Vec<u8>, usize>),
+}
+
+impl TableIndex {
+    pub(crate) fn new<F: PrimeField>(table: &[Vec<F>]) -> Self {
+        let is_range = table
+            .iter()
+            .enumerate()
+            .all(|(i, row)| row.len() == 1 && small_value(&row[0]) == Some(i as u64));
+        if is_range {
+            return TableIndex::Range(table.len());
+        }
+
+        let mut index = HashMap::with_capacity(table.len());
+        for (i, row) in table.iter().enumerate() {
+            index.entry(Self::key(row)).or_insert(i);
+        }
+        TableIndex::Hash(index)
+    }
+
+    fn key<F: PrimeField>(row: &[F]) -> Vec<u8> {
+        row.iter()
+            .flat_map(|value| value.to_repr().as_ref().to_vec())
+            .collect()
+    }
+
+    /// Returns the index of `row` in the table or `None` if it is not in the table
+    pub(crate) fn index<F: PrimeField>(&self, row: &[F]) -> Option<usize> {
+        match self {
+            TableIndex::Range(n) => match row {
+                [value] => small_value(value)
+                    .map(|value| value as usize)
+                    .filter(|value| value < n),
+                _ => None,
+            },
+            TableIndex::Hash(index) => index.get(&Self::key(row)).copied(),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use ff::Field;
+use halo2::plonk::ConstraintSystem;
+
+/// Footprint of a lookup gate, printed after layout with the `info` feature
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GateInfo {
+    pub name: &'static str,
+    /// Number of advice columns in each phase
+    pub advice: Vec<usize>,
+    pub fixed: usize,
+    pub selectors: usize,
+    /// Rows used by the gate and its table
+    pub rows: usize,
+    /// Maximum degree of constraints added by the gate
+    pub degree: usize,
+    pub table_size: usize,
+    /// Number of looked up values or tuples
+    pub lookups: usize,
+    /// Number of table rows for each multiplicity
+    pub multiplicities: BTreeMap<usize, usize>,
+}
+
+impl Display for GateInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: advice {:?}, fixed {}, selectors {}, rows {}, degree {}",
+            self.name, self.advice, self.fixed, self.selectors, self.rows, self.degree
+        )?;
+        write!(
+            f,
+            "  table size {}, lookups {}, multiplicities {:?}",
+            self.table_size, self.lookups, self.multiplicities
+        )
+    }
+}
+
+/// Maximum degree of gates that are created after the first `offset` gates
+pub(crate) fn gate_degree<F: Field>(meta: &ConstraintSystem<F>, offset: usize) -> usize {
+    meta.gates()
+        .iter()
+        .skip(offset)
+        .flat_map(|gate| gate.polynomials().iter())
+        .map(|poly| poly.degree())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Histogram of multiplicities
+pub(crate) fn histogram(multiplicities: impl Iterator<Item = usize>) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    multiplicities.for_each(|m| *histogram.entry(m).or_insert(0) += 1);
+    histogram
+}
@@ -1,4 +1,13 @@
+pub mod bitwise;
+pub mod comparison;
+pub mod copy;
+pub mod error;
+mod index;
+pub mod info;
 pub mod logup;
+pub mod permutation;
+pub mod range;
+pub mod shuffle;
 pub mod subset;
 #[cfg(test)]
 pub mod test;
@@ -9,12 +18,86 @@ use halo2::{
     plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error, Fixed, Selector},
 };
 
+use crate::{error::LookupError, info::GateInfo};
+
 pub type AssignedValue<F> = AssignedCell<Assigned<F>, F>;
 
-pub trait LookupGate<F: PrimeField + Ord, const W: usize>: Clone {
-    fn configure(meta: &mut ConstraintSystem<F>, bit_size: usize) -> Self;
-    fn lookup(&mut self, value: &[Value<F>; W]);
-    fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), Error>;
+/// A value to be looked up, either a fresh witness or a cell that is already assigned
+#[derive(Clone, Debug)]
+pub enum Witness<F: Field> {
+    Value(Value<F>),
+    Assigned(AssignedValue<F>),
+}
+
+impl<F: Field> Witness<F> {
+    pub fn value(&self) -> Value<F> {
+        match self {
+            Witness::Value(value) => *value,
+            Witness::Assigned(assigned) => assigned.value().map(|value| value.evaluate()),
+        }
+    }
+}
+
+impl<F: Field> From<Value<F>> for Witness<F> {
+    fn from(value: Value<F>) -> Self {
+        Witness::Value(value)
+    }
+}
+
+impl<F: Field> From<AssignedValue<F>> for Witness<F> {
+    fn from(assigned: AssignedValue<F>) -> Self {
+        Witness::Assigned(assigned)
+    }
+}
+
+pub trait LookupGate<F: PrimeField>: Clone {
+    /// Configures the gate with `width` lookups per row
+    fn configure(meta: &mut ConstraintSystem<F>, table: Vec<F>, width: usize) -> Self;
+    /// Number of lookups per row
+    fn width(&self) -> usize;
+    /// Looks up `width` values
+    fn lookup(&mut self, value: &[Value<F>]);
+    /// Looks up cells that are already assigned, they are copied into the gate
+    fn lookup_assigned(&mut self, value: &[AssignedValue<F>]);
+    /// Reports columns, rows, degree and multiplicities of the gate
+    fn info(&self) -> GateInfo;
+    /// Returns cells of looked up values in the order of lookups
+    fn layout(
+        &self,
+        ly: &mut impl Layouter<F>,
+    ) -> Result<Vec<Vec<AssignedValue<F>>>, LookupError<F>>;
+}
+
+/// Enforces equality between cells, see `RegionCtx::equal`
+pub trait EqualityGate<F: PrimeField>: Clone {
+    /// Configures the gate with `width` cells per row
+    fn configure(meta: &mut ConstraintSystem<F>, width: usize) -> Self;
+    /// Number of cells per row
+    fn width(&self) -> usize;
+    /// Adds a row of `width` values or assigned cells and returns the index of the row.
+    /// Assigned cells are copied into the gate.
+    fn assign(&mut self, value: &[Witness<F>]) -> usize;
+    /// Constrains the cell at `(row, column)` to be equal to another one
+    fn equal(&mut self, cell_0: (usize, usize), cell_1: (usize, usize));
+    /// Returns assigned cells in the order of rows
+    fn layout(&self, ly: &mut impl Layouter<F>) -> Result<Vec<Vec<AssignedValue<F>>>, Error>;
+}
+
+/// Returns the range table `0..2^bit_size`
+pub fn range_table<F: PrimeField>(bit_size: usize) -> Vec<F> {
+    (0..1u64 << bit_size).map(F::from).collect()
+}
+
+/// Number of rows of `2^k` that are not reserved for blinding
+pub fn usable_rows(k: u32, blinding_factors: usize) -> usize {
+    (1usize << k).saturating_sub(blinding_factors + 1)
+}
+
+/// Smallest `k` that has at least `rows` usable rows
+pub fn min_k(rows: usize, blinding_factors: usize) -> u32 {
+    (1..usize::BITS)
+        .find(|k| usable_rows(*k, blinding_factors) >= rows)
+        .expect("too many rows")
 }
 
 #[derive(Debug)]
@@ -50,6 +133,22 @@ impl<'a, F: Field> RegionCtx<'a, F> {
             .assign_advice(|| "", column, self.offset, || value)
     }
 
+    pub fn assign(
+        &mut self,
+        column: Column<Any>,
+        value: Value<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let value: Value<Assigned<F>> = value.into();
+        match column.column_type() {
+            Any::Advice(_) => self.advice(column.try_into().unwrap(), value),
+            Any::Fixed => {
+                self.region
+                    .assign_fixed(|| "", column.try_into().unwrap(), self.offset, || value)
+            }
+            _ => panic!("Cannot assign to instance column"),
+        }
+    }
+
     pub fn empty(&mut self, column: Column<Any>) -> Result<AssignedValue<F>, Error> {
         match column.column_type() {
             Any::Advice(_) => self.advice(column.try_into().unwrap(), Value::known(F::ZERO.into())),
@@ -66,6 +165,18 @@ impl<'a, F: Field> RegionCtx<'a, F> {
         assigned.copy_advice(|| "", &mut self.region, column, self.offset)
     }
 
+    /// Assigns a fresh witness or copies an assigned one
+    pub fn witness(
+        &mut self,
+        column: Column<Advice>,
+        witness: &Witness<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        match witness {
+            Witness::Value(value) => self.advice(column, value.map(|value| value.into())),
+            Witness::Assigned(assigned) => self.copy(column, assigned),
+        }
+    }
+
     pub fn equal(&mut self, cell_0: Cell, cell_1: Cell) -> Result<(), Error> {
         self.region.constrain_equal(cell_0, cell_1)
     }
@@ -1,175 +1,884 @@
-use std::collections::BTreeMap;
-
-use ff::PrimeField;
+use ff::{Field, PrimeField};
 use halo2::{
     circuit::{Layouter, Value},
-    plonk::{Assigned, ConstraintSystem, Error},
+    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error},
+};
+
+use crate::{
+    error::{Identity, LookupError},
+    info::{histogram, GateInfo},
+    usable_rows, AssignedValue, LookupGate, RegionCtx, Witness,
 };
 
-use crate::{LookupGate, RegionCtx};
+use super::config::{compress, LogupConfig};
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::table::LogupTable;
 
-use super::config::LogupConfig;
+/// Multiplicities, table helpers and witness helpers of each lookup row
+type Helpers<F> = (
+    Vec<Value<Assigned<F>>>,
+    Vec<Value<Assigned<F>>>,
+    Vec<Vec<Value<Assigned<F>>>>,
+);
+
+/// Cells of looked up values in the order of lookups and cells of table rows
+pub type Layout<F> = (Vec<Vec<Vec<AssignedValue<F>>>>, Vec<Vec<AssignedValue<F>>>);
+
+/// Wraps fresh values of `width` tuples into witnesses
+fn fresh<F: Field>(value: &[Vec<Value<F>>]) -> Vec<Vec<Witness<F>>> {
+    value
+        .iter()
+        .map(|value| value.iter().copied().map(Witness::Value).collect())
+        .collect()
+}
 
+/// Number of table and witness rows in each region of a gate with `body_rows` rows
+fn region_rows(body_rows: usize, chunk_rows: Option<usize>) -> usize {
+    chunk_rows.unwrap_or(body_rows).max(1)
+}
+
+/// Number of rows used by `lookup_rows` rows of lookups and `table_rows` rows of each set of
+/// table columns, including the last accumulator row of each region
+fn gate_rows(lookup_rows: usize, table_rows: usize, chunk_rows: Option<usize>) -> usize {
+    let body_rows = std::cmp::max(lookup_rows, table_rows);
+    let chunks = body_rows
+        .div_ceil(region_rows(body_rows, chunk_rows))
+        .max(1);
+    body_rows + chunks
+}
+
+/// Lookups of a single row
 #[derive(Clone, Debug)]
-pub struct LogupGate<F: PrimeField + Ord, const W: usize> {
-    cfg: LogupConfig<F, W>,
-    bit_size: usize,
-    multiplicities: BTreeMap<F, usize>,
-    witnesses: Vec<[Value<F>; W]>,
+struct LookupRow<F: Field> {
+    values: Vec<Vec<Witness<F>>>,
+    /// Flag of a conditional lookup, unconditional lookups are enabled by the gate
+    enable: Option<AssignedValue<F>>,
+    tag: Option<F>,
 }
 
-impl<F: PrimeField + Ord, const W: usize> LookupGate<F, W> for LogupGate<F, W> {
-    fn configure(meta: &mut ConstraintSystem<F>, bit_size: usize) -> Self {
-        let w = std::iter::repeat_with(|| meta.advice_column())
-            .take(W)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+impl<F: Field> LookupRow<F> {
+    /// Value of the enable flag
+    fn enable(&self) -> Value<F> {
+        match &self.enable {
+            Some(enable) => enable.value().map(|enable| enable.evaluate()),
+            None => Value::known(F::ONE),
+        }
+    }
+
+    /// Returns looked up tuples with the tag prepended
+    fn tuples(&self) -> impl Iterator<Item = Vec<Value<F>>> + '_ {
+        self.values.iter().map(|value| {
+            self.tag
+                .map(Value::known)
+                .into_iter()
+                .chain(value.iter().map(|w| w.value()))
+                .collect()
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogupGate<F: PrimeField> {
+    cfg: LogupConfig<F>,
+    table: LogupTable<F>,
+    /// Rows of a dynamic table, assigned cells are copied into the table columns
+    dynamic_table: Vec<Vec<Witness<F>>>,
+    witnesses: Vec<LookupRow<F>>,
+    blinding_factors: usize,
+    k: Option<u32>,
+    chunk_rows: Option<usize>,
+}
+
+impl<F: PrimeField> LogupGate<F> {
+    fn new(
+        meta: &mut ConstraintSystem<F>,
+        width: usize,
+        t: Vec<Vec<Column<Any>>>,
+        table: LogupTable<F>,
+        group_size: usize,
+        tagged: bool,
+    ) -> Self {
+        let w_tag = tagged.then(|| meta.fixed_column());
+        let arity = t[0].len() - tagged as usize;
+        let w = (0..width)
+            .map(|_| (0..arity).map(|_| meta.advice_column()).collect())
+            .collect::<Vec<Vec<Column<Advice>>>>();
 
-        let cfg = LogupConfig::configure(meta, &w);
+        let cfg = LogupConfig::configure(meta, &w, w_tag, &t, group_size);
 
         Self {
             cfg,
-            bit_size,
-            multiplicities: BTreeMap::new(),
+            table,
+            dynamic_table: Vec::new(),
             witnesses: Vec::new(),
+            blinding_factors: meta.blinding_factors(),
+            k: None,
+            chunk_rows: None,
         }
     }
 
-    fn lookup(&mut self, value: &[Value<F>; W]) {
-        self.witnesses.push(*value);
-        value.iter().for_each(|value| {
-            value.map(|value| {
-                self.multiplicities
-                    .entry(value)
-                    .and_modify(|e| *e += 1)
-                    .or_insert(1);
+    /// Smallest `k` that fits `lookups` values looked up `width` at a time into a table of
+    /// `table_size` rows spread over `sets` sets of columns, with regions of at most
+    /// `chunk_rows` rows if given, see `set_chunk_rows`. Assumes that no advice column of the
+    /// circuit is queried more than three times, see `ConstraintSystem::blinding_factors`.
+    pub fn min_k(
+        table_size: usize,
+        width: usize,
+        lookups: usize,
+        sets: usize,
+        chunk_rows: Option<usize>,
+    ) -> u32 {
+        assert!(width > 0 && sets > 0);
+        let rows = gate_rows(
+            lookups.div_ceil(width),
+            table_size.div_ceil(sets),
+            chunk_rows,
+        );
+        // acc is queried at two rotations
+        crate::min_k(rows, 5)
+    }
+
+    /// Smallest `k` that fits the table and lookups added so far, blinding rows are counted as
+    /// of the time the gate is configured
+    pub fn required_k(&self) -> u32 {
+        crate::min_k(self.rows(), self.blinding_factors)
+    }
+
+    /// Number of column sets that fit a table of `table_size` rows into `2^k`, see
+    /// `configure_split`
+    pub fn table_sets_for_k(table_size: usize, k: u32) -> usize {
+        // last row is for the accumulator
+        let rows = usable_rows(k, 5).saturating_sub(1);
+        assert!(rows > 0, "no rows left for the table with k = {k}");
+        table_size.div_ceil(rows)
+    }
+
+    /// Splits the running sum into regions of at most `chunk_rows` table and witness rows.
+    /// Regions are linked with copy constraints on the accumulator so that the floor planner
+    /// is free to place them.
+    pub fn set_chunk_rows(&mut self, chunk_rows: usize) {
+        assert!(chunk_rows > 0);
+        self.chunk_rows = Some(chunk_rows);
+    }
+
+    /// Sets the size of the circuit so that `layout` fails before assigning any cell if the
+    /// gate doesn't fit. Otherwise the same error is returned once a region runs out of rows.
+    pub fn set_k(&mut self, k: u32) {
+        self.k = Some(k);
+    }
+
+    /// Configures the gate for `width` tuple lookups per row where each entry of `table` is a
+    /// row of the table
+    pub fn configure_tuple(
+        meta: &mut ConstraintSystem<F>,
+        table: Vec<Vec<F>>,
+        width: usize,
+    ) -> Self {
+        Self::configure_grouped(meta, table, width, 1)
+    }
+
+    /// Configures the gate for tuple lookups where every `group_size` lookups in a row share a
+    /// single helper column. See `LogupConfig::group_size_for_degree`.
+    pub fn configure_grouped(
+        meta: &mut ConstraintSystem<F>,
+        table: Vec<Vec<F>>,
+        width: usize,
+        group_size: usize,
+    ) -> Self {
+        Self::configure_with_table(meta, &LogupTable::new(table), width, group_size, 1)
+    }
+
+    /// Configures the gate for tuple lookups where the table is spread over `sets` sets of
+    /// columns so that a table larger than the circuit can be used
+    pub fn configure_split(
+        meta: &mut ConstraintSystem<F>,
+        table: Vec<Vec<F>>,
+        width: usize,
+        sets: usize,
+    ) -> Self {
+        Self::configure_with_table(meta, &LogupTable::new(table), width, 1, sets)
+    }
+
+    /// Configures the gate over a table built beforehand so that rows of the table and their
+    /// index are shared with other gates and are not rebuilt for each proof. See
+    /// `configure_grouped` and `configure_split` for `group_size` and `sets`.
+    pub fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        table: &LogupTable<F>,
+        width: usize,
+        group_size: usize,
+        sets: usize,
+    ) -> Self {
+        assert!(sets > 0);
+        let t = (0..sets)
+            .map(|_| {
+                (0..table.arity())
+                    .map(|_| meta.fixed_column().into())
+                    .collect()
+            })
+            .collect();
+        Self::new(meta, width, t, table.clone(), group_size, table.is_tagged())
+    }
+
+    /// Configures the gate for lookups into many tables that share the same argument.
+    /// Table `i` is tagged with `i`, tables of different arities are padded with zeros.
+    pub fn configure_tagged(
+        meta: &mut ConstraintSystem<F>,
+        tables: Vec<Vec<Vec<F>>>,
+        width: usize,
+    ) -> Self {
+        Self::configure_with_table(meta, &LogupTable::tagged(tables), width, 1, 1)
+    }
+
+    /// Configures the gate for tuple lookups into a table that lives in first phase advice
+    /// columns. Table content is given with `assign_table` during synthesis.
+    pub fn configure_dynamic(meta: &mut ConstraintSystem<F>, arity: usize, width: usize) -> Self {
+        let t = (0..arity)
+            .map(|_| {
+                let t = meta.advice_column();
+                // table can be copied from other regions
+                meta.enable_equality(t);
+                t.into()
+            })
+            .collect();
+        Self::new(meta, width, vec![t], LogupTable::default(), 1, false)
+    }
+
+    /// Sets the rows of a dynamic table where entries are either fresh values or cells that
+    /// are copied into the table. Number of rows must not depend on the witness so that keygen
+    /// and proving end up with the same layout.
+    pub fn assign_table(&mut self, table: Vec<Vec<Witness<F>>>) {
+        assert!(self.is_dynamic());
+        let arity = self.cfg.t[0].len();
+        assert!(table.iter().all(|t| t.len() == arity));
+        let rows = table
+            .iter()
+            .map(|row| row.iter().map(Witness::value).collect())
+            .collect();
+        self.table = LogupTable::from_rows(rows, arity, false);
+        self.dynamic_table = table;
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.cfg
+            .t
+            .iter()
+            .flatten()
+            .any(|t| matches!(t.column_type(), Any::Advice(_)))
+    }
+
+    /// Looks up `width` tuples, each of them must be a row of the table
+    pub fn lookup_tuple(&mut self, value: &[Vec<Value<F>>]) {
+        self.lookup_witness(&fresh(value), None)
+    }
+
+    /// Looks up `width` tuples if `enable` is one. If `enable` is zero tuples are not required to be
+    /// in the table and they add nothing to the grand sum. `enable` is constrained to be boolean
+    /// and is copied from the cell where the caller computes the condition, e.g. `q * w`.
+    pub fn lookup_tuple_conditional(&mut self, value: &[Vec<Value<F>>], enable: &AssignedValue<F>) {
+        self.lookup_witness(&fresh(value), Some(enable))
+    }
+
+    /// Looks up `width` values if `enable` is one
+    pub fn lookup_conditional(&mut self, value: &[Value<F>], enable: &AssignedValue<F>) {
+        let value = value.iter().map(|value| vec![*value]).collect::<Vec<_>>();
+        self.lookup_tuple_conditional(&value, enable)
+    }
+
+    /// Most general form of lookup where values can be either fresh values or assigned cells.
+    /// Assigned cells are copied into the gate. Lookups are conditional only if `enable` is
+    /// given, otherwise the gate pins the flag to one.
+    pub fn lookup_witness(&mut self, value: &[Vec<Witness<F>>], enable: Option<&AssignedValue<F>>) {
+        assert!(
+            self.cfg.w_tag.is_none(),
+            "use lookup_tagged for tagged tables"
+        );
+        self.push(LookupRow {
+            values: value.to_vec(),
+            enable: enable.cloned(),
+            tag: None,
+        })
+    }
+
+    /// Looks up `width` tuples in the table tagged with `tag`
+    pub fn lookup_tagged(&mut self, tag: usize, value: &[Vec<Value<F>>]) {
+        self.lookup_tagged_witness(tag, &fresh(value), None)
+    }
+
+    /// Looks up `width` tuples in the table tagged with `tag`, conditionally if `enable` is
+    /// given, see `lookup_witness`
+    pub fn lookup_tagged_witness(
+        &mut self,
+        tag: usize,
+        value: &[Vec<Witness<F>>],
+        enable: Option<&AssignedValue<F>>,
+    ) {
+        assert!(self.cfg.w_tag.is_some(), "gate is not configured with tags");
+        let arity = self.cfg.t[0].len() - 1;
+        // pad to the widest table
+        let values = value
+            .iter()
+            .map(|value| {
+                assert!(value.len() <= arity);
+                value
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::repeat(Witness::Value(Value::known(F::ZERO))))
+                    .take(arity)
+                    .collect()
+            })
+            .collect();
+        self.push(LookupRow {
+            values,
+            enable: enable.cloned(),
+            tag: Some(F::from(tag as u64)),
+        })
+    }
+
+    fn push(&mut self, row: LookupRow<F>) {
+        assert_eq!(row.values.len(), self.width());
+        assert!(row.values.iter().all(|w| w.len() == self.cfg.w[0].len()));
+        self.witnesses.push(row);
+    }
+
+    /// Finds multiplicities of table rows, repeated table entries after the first one get zero
+    fn table_multiplicities(&self) -> Vec<Value<u64>> {
+        let index = match self.table.index() {
+            Some(index) => index,
+            None => return vec![Value::unknown(); self.table.len()],
+        };
+        let mut counts = vec![0u64; self.table.len()];
+        for row in self.witnesses.iter() {
+            let q = row.enable();
+            row.tuples().for_each(|value| {
+                let value: Value<Vec<F>> = value.into_iter().collect();
+                value.zip(q).map(|(value, enable)| {
+                    if enable != F::ZERO {
+                        if let Some(i) = index.index(&value) {
+                            counts[i] += 1;
+                        }
+                    }
+                });
             });
-        });
+        }
+        counts.into_iter().map(Value::known).collect()
     }
 
-    fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
-        let alpha: Value<F> = ly.get_challenge(self.cfg.alpha);
+    /// Number of rows of each set of table columns
+    fn table_rows(&self) -> usize {
+        self.table.len().div_ceil(self.cfg.t.len())
+    }
+
+    /// Number of rows of table and witnesses
+    fn body_rows(&self) -> usize {
+        std::cmp::max(self.witnesses.len(), self.table_rows())
+    }
+
+    /// Number of table and witness rows in each region
+    fn chunk_rows(&self) -> usize {
+        region_rows(self.body_rows(), self.chunk_rows)
+    }
+
+    /// Number of regions that the gate is laid out in
+    fn chunks(&self) -> usize {
+        self.body_rows().div_ceil(self.chunk_rows()).max(1)
+    }
+
+    /// Number of rows used by the gate including the last accumulator row of each region
+    fn rows(&self) -> usize {
+        gate_rows(self.witnesses.len(), self.table_rows(), self.chunk_rows)
+    }
+
+    /// Table padded with its first entry so that every set of table columns is full
+    pub(crate) fn padded_table(&self) -> Vec<&Vec<Value<F>>> {
+        let rows = self.table.rows();
+        rows.iter()
+            .chain(rows.iter().take(1).cycle())
+            .take(self.table_rows() * self.cfg.t.len())
+            .collect()
+    }
 
-        let table = (0..1 << self.bit_size).map(F::from).collect::<Vec<_>>();
+    /// Finds multiplicities, table helpers and witness helpers of each lookup. Inverses are left
+    /// to halo2 to be batch inverted when cells are assigned.
+    #[cfg_attr(feature = "parallel", allow(dead_code))]
+    pub(crate) fn helpers(
+        &self,
+        table: &[&Vec<Value<F>>],
+        alpha: Value<F>,
+        beta: Value<F>,
+    ) -> Helpers<F> {
+        // multiplicities are in first phase so they must only depend on the table and witnesses.
+        // padding entries get zero
+        let multiplicities = self
+            .table_multiplicities()
+            .into_iter()
+            .chain(std::iter::repeat(Value::known(0)))
+            .take(table.len())
+            .map(|m| {
+                let m: Value<F> = m.map(F::from);
+                let m: Value<Assigned<F>> = m.into();
+                m
+            })
+            .collect::<Vec<_>>();
 
         // find witness helpers
-        let w_helper: Vec<[Value<Assigned<F>>; W]> = self
+        let w_inv: Vec<Vec<Value<Assigned<F>>>> = self
             .witnesses
             .iter()
-            .enumerate()
-            .map(|(_i, w)| {
-                w.iter()
+            .map(|row| {
+                let q = row.enable();
+                row.tuples()
                     .map(|w| {
-                        // w_helper_i = 1 / (alpha - w_i)
-                        (alpha - w).map(|inv| Assigned::Rational(F::ONE, inv))
+                        let w = compress(w.into_iter(), beta);
+                        // w_helper_i = q / (alpha - w_i)
+                        (alpha - w)
+                            .zip(q)
+                            .map(|(inv, q)| Assigned::Rational(q, inv))
                     })
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap()
+                    .collect()
             })
             .collect::<Vec<_>>();
 
         // find table helpers
         let t_helper = table
             .iter()
-            .enumerate()
-            // t_helper_i = 1 / (alpha - t_i)
-            .map(|(_i, t)| (alpha - Value::known(*t)).map(|inv| Assigned::Rational(F::ONE, inv)))
+            .map(|t| {
+                let t = compress(t.iter().copied(), beta);
+                // t_helper_i = 1 / (alpha - t_i)
+                (alpha - t).map(|inv| Assigned::Rational(F::ONE, inv))
+            })
             .collect::<Vec<_>>();
 
-        // find multiplicities
-        let multiplicities = table
+        (multiplicities, t_helper, w_inv)
+    }
+
+    /// Same as `helpers` but computed across threads where inverses are found explicitly with
+    /// batch inversion. Values are computed only if the table and all lookups are known.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn helpers_parallel(
+        &self,
+        table: &[&Vec<Value<F>>],
+        alpha: Value<F>,
+        beta: Value<F>,
+    ) -> Helpers<F> {
+        let width = self.width();
+        let n_table = table.len();
+        let table: Value<Vec<Vec<F>>> = table
             .iter()
-            .enumerate()
-            .map(|(_i, t)| {
-                let m: F = (*self.multiplicities.get(t).unwrap_or(&0) as u64).into();
-                let m: Value<Assigned<F>> = Value::known(m).into();
+            .map(|t| t.iter().copied().collect::<Value<Vec<F>>>())
+            .collect();
+        let tuples: Value<Vec<Vec<F>>> = self
+            .witnesses
+            .iter()
+            .flat_map(|row| row.tuples())
+            .map(|w| w.into_iter().collect::<Value<Vec<F>>>())
+            .collect();
+        let enable: Value<Vec<F>> = self
+            .witnesses
+            .iter()
+            .flat_map(|row| std::iter::repeat(row.enable()).take(width))
+            .collect();
+
+        // padding entries get zero
+        let multiplicities = match self.table.index() {
+            Some(index) => tuples
+                .as_ref()
+                .zip(enable.as_ref())
+                .map(|(tuples, enable)| {
+                    parallel::multiplicities(index, self.table.len(), tuples, enable)
+                }),
+            None => Value::unknown(),
+        };
+        let multiplicities = multiplicities
+            .transpose_vec(self.table.len())
+            .into_iter()
+            .chain(std::iter::repeat(Value::known(0)))
+            .take(n_table)
+            .map(|m| {
+                let m: Value<F> = m.map(F::from);
+                let m: Value<Assigned<F>> = m.into();
                 m
             })
             .collect::<Vec<_>>();
 
-        ly.assign_region(
-            || "assign",
-            |region| {
-                let mut ctx = RegionCtx::new(region);
+        // t_helper_i = 1 / (alpha - t_i)
+        let t_helper = alpha
+            .zip(beta)
+            .zip(table)
+            .map(|((alpha, beta), table)| {
+                parallel::helpers(alpha, beta, &table, &vec![F::ONE; table.len()])
+            })
+            .transpose_vec(n_table);
 
-                let acc_off = std::cmp::max(self.witnesses.len(), table.len());
+        // w_helper_i = q / (alpha - w_i)
+        let w_inv = alpha
+            .zip(beta)
+            .zip(tuples)
+            .zip(enable)
+            .map(|(((alpha, beta), tuples), enable)| {
+                parallel::helpers(alpha, beta, &tuples, &enable)
+            })
+            .transpose_vec(self.witnesses.len() * width)
+            .chunks(width)
+            .map(|h| h.to_vec())
+            .collect();
 
-                let multiplicities = multiplicities
-                    .iter()
-                    .map(Some)
-                    .chain(std::iter::repeat(None))
-                    .take(acc_off);
-                let table = table
-                    .iter()
-                    .zip(t_helper.iter())
-                    .map(Some)
-                    .chain(std::iter::repeat(None))
-                    .take(acc_off);
-                let witneses = self
-                    .witnesses
-                    .iter()
-                    .zip(w_helper.iter())
-                    .map(Some)
-                    .chain(std::iter::repeat(None))
-                    .take(acc_off);
+        (multiplicities, t_helper, w_inv)
+    }
 
-                // init acc to zero
-                let mut acc: Value<Assigned<F>> = Value::known(F::ZERO).into();
-                ctx.enable(self.cfg.s_zero)?;
+    /// Finds helpers that are not inverses since alpha collides with a table or a witness value
+    pub(crate) fn check_collisions(
+        &self,
+        t_helper: &[Value<Assigned<F>>],
+        w_helper: &[Vec<Value<Assigned<F>>>],
+    ) -> Result<(), LookupError<F>> {
+        // disabled lookups have zero numerator and don't need an inverse
+        let collides = |h: &Value<Assigned<F>>| {
+            let mut collides = false;
+            h.map(|h| collides = h.numerator() != F::ZERO && h.denominator() == Some(F::ZERO));
+            collides
+        };
+        if let Some(row) = t_helper.iter().position(collides) {
+            return Err(LookupError::AlphaCollision(Identity::TableHelper { row }));
+        }
+        for (lookup, h) in w_helper.iter().enumerate() {
+            if let Some(column) = h.iter().position(collides) {
+                return Err(LookupError::AlphaCollision(Identity::WitnessHelper {
+                    lookup,
+                    column,
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every enabled and known lookup is in the table. Lookups into a dynamic table
+    /// are checked only if the table is known.
+    pub fn check_witnesses(&self) -> Result<(), LookupError<F>> {
+        let index = match self.table.index() {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        self.witnesses
+            .iter()
+            .enumerate()
+            .try_for_each(|(lookup, row)| {
+                row.tuples().enumerate().try_for_each(|(column, value)| {
+                    let value: Value<Vec<F>> = value.into_iter().collect();
+                    let mut result = Ok(());
+                    value.zip(row.enable()).map(|(value, enable)| {
+                        if enable != F::ZERO && index.index(&value).is_none() {
+                            result = Err(LookupError::NotInTable {
+                                lookup,
+                                column,
+                                value,
+                            });
+                        }
+                    });
+                    result
+                })
+            })
+    }
 
-                for (_i, ((t, w), m)) in table.zip(witneses).zip(multiplicities).enumerate() {
-                    ctx.enable(self.cfg.s_acc)?;
-                    ctx.advice(self.cfg.acc, acc)?;
+    /// Checks the grand sum out of circuit on the final witness so that a failing lookup is
+    /// reported before the proof is created. Helpers are inverses by construction once
+    /// `check_collisions` passes so only the sum can fail.
+    #[cfg(feature = "prover-sanity")]
+    pub(crate) fn check_identities(
+        multiplicities: &[Value<Assigned<F>>],
+        t_helper: &[Value<Assigned<F>>],
+        w_helper: &[Vec<Value<Assigned<F>>>],
+    ) -> Result<(), LookupError<F>> {
+        // sum_i m_i / (alpha - t_i) = sum_j q_j / (alpha - w_j)
+        let lhs = multiplicities
+            .iter()
+            .zip(t_helper.iter())
+            .fold(Value::known(F::ZERO), |acc, (m, h)| {
+                acc + (*m * *h).evaluate()
+            });
+        let rhs = w_helper
+            .iter()
+            .flatten()
+            .fold(Value::known(F::ZERO), |acc, h| acc + h.evaluate());
+
+        let mut result = Ok(());
+        lhs.zip(rhs).map(|(lhs, rhs)| {
+            if lhs != rhs {
+                result = Err(LookupError::Identity(Identity::GrandSum));
+            }
+        });
+        result
+    }
 
-                    match (t, m) {
-                        (Some((t, h)), Some(m)) => {
-                            ctx.enable(self.cfg.s_table)?;
+    /// Lays out the gate and returns cells of looked up tuples in the order of lookups
+    pub fn layout_tuple(
+        &self,
+        ly: &mut impl Layouter<F>,
+    ) -> Result<Vec<Vec<Vec<AssignedValue<F>>>>, LookupError<F>> {
+        self.layout_with_table(ly).map(|(assigned, _)| assigned)
+    }
 
-                            ctx.fixed(self.cfg.t, *t)?;
-                            ctx.advice(self.cfg.t_helper, *h)?;
-                            ctx.advice(self.cfg.m, *m)?;
+    /// Same as `layout_tuple` but also returns cells of table rows so that a dynamic table can
+    /// be constrained further. Padding rows are not returned.
+    pub fn layout_with_table(
+        &self,
+        ly: &mut impl Layouter<F>,
+    ) -> Result<Layout<F>, LookupError<F>> {
+        let rows = self.rows();
+        if let Some(k) = self.k {
+            if rows > usable_rows(k, self.blinding_factors) {
+                return Err(LookupError::NotEnoughRows { required: rows, k });
+            }
+        }
 
-                            acc = acc + *h * m;
-                        }
-                        (None, None) => {
-                            ctx.empty(self.cfg.t_helper.into())?;
-                            ctx.empty(self.cfg.m.into())?;
+        #[cfg(feature = "synth-sanity")]
+        self.check_witnesses()?;
+
+        let table = self.padded_table();
+        let alpha: Value<F> = ly.get_challenge(self.cfg.alpha);
+        let beta: Value<F> = ly.get_challenge(self.cfg.beta);
+
+        #[cfg(not(feature = "parallel"))]
+        let (multiplicities, t_helper, w_inv) = self.helpers(&table, alpha, beta);
+        #[cfg(feature = "parallel")]
+        let (multiplicities, t_helper, w_inv) = self.helpers_parallel(&table, alpha, beta);
+
+        // sum helpers in the same group
+        let w_helper: Vec<Vec<Value<Assigned<F>>>> = w_inv
+            .iter()
+            .map(|w_inv| {
+                w_inv
+                    .chunks(self.cfg.group_size)
+                    .map(|h| {
+                        h.iter()
+                            .fold(Value::known(Assigned::Zero), |acc, h| acc + *h)
+                    })
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        self.check_collisions(&t_helper, &w_inv)?;
+        #[cfg(feature = "prover-sanity")]
+        Self::check_identities(&multiplicities, &t_helper, &w_inv)?;
+
+        let table_rows = self.table_rows();
+        let body_rows = self.body_rows();
+        let chunk_rows = self.chunk_rows();
+        let chunks = self.chunks();
+
+        let mut assigned = Vec::with_capacity(self.witnesses.len());
+        let mut table_cells = Vec::with_capacity(self.table.len());
+        let mut acc: Value<Assigned<F>> = Value::known(F::ZERO).into();
+        // last accumulator cell of the previous chunk
+        let mut boundary: Option<AssignedValue<F>> = None;
+        for chunk in 0..chunks {
+            let start = chunk * chunk_rows;
+            let end = std::cmp::min(start + chunk_rows, body_rows);
+            let result = ly.assign_region(
+                || "assign",
+                |region| {
+                    let mut ctx = RegionCtx::new(region);
+                    let mut assigned = Vec::with_capacity(end - start);
+                    let mut table_cells = Vec::new();
+                    let mut acc = acc;
+
+                    match &boundary {
+                        // init acc to zero
+                        None => ctx.enable(self.cfg.s_zero)?,
+                        // continue from the previous chunk
+                        Some(boundary) => {
+                            ctx.copy(self.cfg.acc, boundary)?;
                         }
-                        _ => unreachable!(),
                     }
 
-                    match w {
-                        Some((w, h)) => {
-                            for (i, (w, h)) in w.iter().zip(h.iter()).enumerate() {
-                                let w: Value<Assigned<F>> = (*w).into();
-                                ctx.enable(self.cfg.s_witness)?;
+                    for i in start..end {
+                        ctx.enable(self.cfg.s_acc)?;
+                        if i != start || boundary.is_none() {
+                            ctx.advice(self.cfg.acc, acc)?;
+                        }
 
-                                ctx.advice(self.cfg.w[i], w)?;
-                                ctx.advice(self.cfg.w_helper[i], *h)?;
+                        match i < table_rows {
+                            true => {
+                                ctx.enable(self.cfg.s_table)?;
 
-                                acc = acc - h;
+                                // set j holds entries from j * table_rows
+                                for (j, columns) in self.cfg.t.iter().enumerate() {
+                                    let i = j * table_rows + i;
+                                    // padding entries are copies of the first row
+                                    let source = match i < self.table.len() {
+                                        true => i,
+                                        false => 0,
+                                    };
+                                    let cells = columns
+                                        .iter()
+                                        .zip(table[i].iter())
+                                        .enumerate()
+                                        .map(|(c, (column, t))| {
+                                            match self.dynamic_table.get(source) {
+                                                Some(row) => ctx.witness(
+                                                    (*column).try_into().unwrap(),
+                                                    &row[c],
+                                                ),
+                                                None => ctx.assign(*column, *t),
+                                            }
+                                        })
+                                        .collect::<Result<Vec<_>, Error>>()?;
+                                    if i < self.table.len() {
+                                        table_cells.push((i, cells));
+                                    }
+                                    ctx.advice(self.cfg.t_helper[j], t_helper[i])?;
+                                    ctx.advice(self.cfg.m[j], multiplicities[i])?;
+
+                                    acc = acc + t_helper[i] * multiplicities[i];
+                                }
+                            }
+                            false => {
+                                for column in self.cfg.t_helper.iter().chain(self.cfg.m.iter()) {
+                                    ctx.empty((*column).into())?;
+                                }
                             }
                         }
-                        _ => {
-                            for i in 0..W {
-                                ctx.empty(self.cfg.w[i].into())?;
-                                ctx.empty(self.cfg.w_helper[i].into())?;
+
+                        match self.witnesses.get(i).zip(w_helper.get(i)) {
+                            Some((w, h)) => {
+                                match &w.enable {
+                                    Some(enable) => {
+                                        ctx.copy(self.cfg.q, enable)?;
+                                        ctx.fixed(self.cfg.conditional, F::ONE)?;
+                                    }
+                                    // flag is pinned to one by the gate
+                                    None => {
+                                        ctx.advice(self.cfg.q, Value::known(F::ONE).into())?;
+                                    }
+                                }
+                                ctx.enable(self.cfg.s_witness)?;
+                                if let (Some(column), Some(tag)) = (self.cfg.w_tag, w.tag) {
+                                    ctx.fixed(column, tag)?;
+                                }
+                                let mut row = Vec::with_capacity(self.width());
+                                for (columns, w) in self.cfg.w.iter().zip(w.values.iter()) {
+                                    let w = columns
+                                        .iter()
+                                        .zip(w.iter())
+                                        .map(|(column, w)| ctx.witness(*column, w))
+                                        .collect::<Result<Vec<_>, Error>>()?;
+                                    row.push(w);
+                                }
+                                assigned.push(row);
+
+                                for (column, h) in self.cfg.w_helper.iter().zip(h.iter()) {
+                                    ctx.advice(*column, *h)?;
+                                    acc = acc - h;
+                                }
+                            }
+                            None => {
+                                ctx.empty(self.cfg.q.into())?;
+                                for column in self.cfg.w.iter().flatten() {
+                                    ctx.empty((*column).into())?;
+                                }
+                                for column in self.cfg.w_helper.iter() {
+                                    ctx.empty((*column).into())?;
+                                }
                             }
                         }
+                        ctx.next();
+                    }
+
+                    let last = ctx.advice(self.cfg.acc, acc)?;
+                    if chunk + 1 == chunks {
+                        ctx.enable(self.cfg.s_zero)?;
                     }
-                    ctx.next();
-                }
 
-                ctx.advice(self.cfg.acc, acc)?;
-                ctx.enable(self.cfg.s_zero)?;
-                acc.map(|acc| assert_eq!(acc.evaluate(), F::ZERO));
+                    Ok((assigned, table_cells, last, acc))
+                },
+            );
+            let (cells, rows, last, next) = result.map_err(|err| match err {
+                Error::NotEnoughRowsAvailable { current_k } => LookupError::NotEnoughRows {
+                    required: rows,
+                    k: current_k,
+                },
+                err => err.into(),
+            })?;
+            assigned.extend(cells);
+            table_cells.extend(rows);
+            boundary = Some(last);
+            acc = next;
+        }
+        #[cfg(feature = "info")]
+        println!("{}", self.info());
 
-                Ok(())
-            },
-        )?;
+        // sets of table columns are filled one after another
+        table_cells.sort_by_key(|(i, _)| *i);
+        let table_cells = table_cells.into_iter().map(|(_, cells)| cells).collect();
+        Ok((assigned, table_cells))
+    }
+}
 
-        Ok(())
+impl<F: PrimeField> LookupGate<F> for LogupGate<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, table: Vec<F>, width: usize) -> Self {
+        Self::configure_tuple(meta, table.into_iter().map(|t| vec![t]).collect(), width)
+    }
+
+    fn width(&self) -> usize {
+        self.cfg.w.len()
+    }
+
+    fn lookup(&mut self, value: &[Value<F>]) {
+        let value = value.iter().map(|value| vec![*value]).collect::<Vec<_>>();
+        self.lookup_tuple(&value)
+    }
+
+    fn info(&self) -> GateInfo {
+        let table_advice = self
+            .cfg
+            .t
+            .iter()
+            .flatten()
+            .filter(|t| matches!(t.column_type(), Any::Advice(_)))
+            .count();
+        let mut multiplicities = Vec::new();
+        self.table_multiplicities().into_iter().for_each(|m| {
+            m.map(|m| multiplicities.push(m as usize));
+        });
+        GateInfo {
+            name: "logup",
+            advice: vec![
+                // w, m, q and dynamic table
+                self.cfg.w.iter().flatten().count() + self.cfg.m.len() + 1 + table_advice,
+                // t_helper, w_helper and acc
+                self.cfg.t_helper.len() + self.cfg.w_helper.len() + 1,
+            ],
+            // table, conditional flag and tag
+            fixed: self.cfg.t.iter().flatten().count() - table_advice
+                + 1
+                + self.cfg.w_tag.is_some() as usize,
+            selectors: 4,
+            rows: self.rows(),
+            degree: self.cfg.degree,
+            table_size: self.table.len(),
+            lookups: self.witnesses.len() * self.width(),
+            multiplicities: histogram(multiplicities.into_iter()),
+        }
+    }
+
+    fn lookup_assigned(&mut self, value: &[AssignedValue<F>]) {
+        let value = value
+            .iter()
+            .map(|value| vec![Witness::Assigned(value.clone())])
+            .collect::<Vec<_>>();
+        self.lookup_witness(&value, None)
+    }
+
+    fn layout(
+        &self,
+        ly: &mut impl Layouter<F>,
+    ) -> Result<Vec<Vec<AssignedValue<F>>>, LookupError<F>> {
+        assert_eq!(self.cfg.t[0].len(), 1, "use layout_tuple for tuple lookups");
+        let assigned = self.layout_tuple(ly)?;
+        Ok(assigned
+            .into_iter()
+            .map(|row| row.into_iter().flatten().collect())
+            .collect())
     }
 }
@@ -1,22 +1,44 @@
 use ff::PrimeField;
 use halo2::{
     plonk::{
-        Advice, Challenge, Column, ConstraintSystem, Constraints, Expression, FirstPhase, Fixed,
-        SecondPhase, Selector,
+        Advice, Any, Challenge, Column, ConstraintSystem, Constraints, Expression, FirstPhase,
+        Fixed, SecondPhase, Selector,
     },
     poly::Rotation,
 };
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    ops::{Add, Mul},
+};
+
+use crate::info::gate_degree;
+
+/// Compresses a tuple into a single value as `x_0 + beta * x_1 + beta^2 * x_2 + ...`
+pub(crate) fn compress<T: Clone + Add<Output = T> + Mul<Output = T>>(
+    values: impl DoubleEndedIterator<Item = T>,
+    beta: T,
+) -> T {
+    values
+        .rev()
+        .reduce(|acc, value| acc * beta.clone() + value)
+        .expect("tuple is not empty")
+}
 
 #[derive(Clone, Debug)]
-pub struct LogupConfig<F: PrimeField, const W: usize> {
-    pub(crate) w: [Column<Advice>; W],
-    pub(crate) t: Column<Fixed>,
-    pub(crate) t_helper: Column<Advice>,
-    pub(crate) w_helper: [Column<Advice>; W],
-    pub(crate) m: Column<Advice>,
+pub struct LogupConfig<F: PrimeField> {
+    pub(crate) w: Vec<Vec<Column<Advice>>>,
+    pub(crate) w_tag: Option<Column<Fixed>>,
+    pub(crate) t: Vec<Vec<Column<Any>>>,
+    pub(crate) t_helper: Vec<Column<Advice>>,
+    pub(crate) w_helper: Vec<Column<Advice>>,
+    pub(crate) group_size: usize,
+    pub(crate) degree: usize,
+    pub(crate) m: Vec<Column<Advice>>,
+    pub(crate) q: Column<Advice>,
+    pub(crate) conditional: Column<Fixed>,
     pub(crate) acc: Column<Advice>,
     pub(crate) alpha: Challenge,
+    pub(crate) beta: Challenge,
 
     pub(crate) s_zero: Selector,
     pub(crate) s_acc: Selector,
@@ -27,20 +49,62 @@ pub struct LogupConfig<F: PrimeField, const W: usize> {
     pub(crate) marker: PhantomData<F>,
 }
 
-impl<F: PrimeField, const W: usize> LogupConfig<F, W> {
-    pub fn configure(meta: &mut ConstraintSystem<F>, w: &[Column<Advice>; W]) -> Self {
-        let t = meta.fixed_column();
-        let m = meta.advice_column_in(SecondPhase);
-        let t_helper = meta.advice_column_in(SecondPhase);
-        let w_helper: [Column<Advice>; W] =
-            std::iter::repeat_with(|| meta.advice_column_in(SecondPhase))
-                .take(W)
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap();
+impl<F: PrimeField> LogupConfig<F> {
+    /// Largest group size that keeps the witness helper gate within `degree`
+    pub fn group_size_for_degree(degree: usize) -> usize {
+        assert!(degree >= 3, "logup gate requires degree 3 at least");
+        degree - 2
+    }
+
+    /// Each of `w.len()` lookups in a row is a tuple of `w[i].len()` columns and is checked
+    /// against the rows of `t`. Table can be spread over many sets of columns `t[j]` where each
+    /// set has its own multiplicity and helper column.
+    /// Table columns are either fixed or first phase advice columns.
+    /// If `w_tag` is given, it is prepended to every tuple in the row and `t[j][0]` is expected
+    /// to be the tag column of the table so that many tables can share the same argument.
+    /// Every `group_size` lookups share a single helper column and the helper gate is of
+    /// degree `group_size + 2`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        w: &[Vec<Column<Advice>>],
+        w_tag: Option<Column<Fixed>>,
+        t: &[Vec<Column<Any>>],
+        group_size: usize,
+    ) -> Self {
+        assert!(group_size > 0);
+        assert!(!t.is_empty() && !t[0].is_empty());
+        let n_gates = meta.gates().len();
+        assert!(t.iter().all(|t| t.len() == t[0].len()));
+        let arity = t[0].len() - w_tag.is_some() as usize;
+        assert!(w.iter().all(|w| w.len() == arity));
+        assert!(t.iter().flatten().all(|t| match t.column_type() {
+            Any::Advice(advice) => advice.phase() == 0,
+            Any::Fixed => true,
+            _ => false,
+        }));
+        // multiplicities are committed before alpha is drawn
+        let m = std::iter::repeat_with(|| meta.advice_column())
+            .take(t.len())
+            .collect::<Vec<_>>();
+        // lookup enable flag, it is free only on rows where `conditional` is set
+        let q = meta.advice_column();
+        let conditional = meta.fixed_column();
+        // looked up values and flags can be copied from other regions
+        w.iter().flatten().for_each(|w| meta.enable_equality(*w));
+        meta.enable_equality(q);
+        let t_helper = std::iter::repeat_with(|| meta.advice_column_in(SecondPhase))
+            .take(t.len())
+            .collect::<Vec<_>>();
+        let w_helper = std::iter::repeat_with(|| meta.advice_column_in(SecondPhase))
+            .take(w.len().div_ceil(group_size))
+            .collect::<Vec<_>>();
         let acc = meta.advice_column_in(SecondPhase);
+        // accumulator can be split into regions
+        meta.enable_equality(acc);
 
         let alpha = meta.challenge_usable_after(FirstPhase);
+        // tuple compression
+        let beta = meta.challenge_usable_after(FirstPhase);
 
         let s_zero = meta.selector();
         let s_acc = meta.selector();
@@ -48,28 +112,70 @@ impl<F: PrimeField, const W: usize> LogupConfig<F, W> {
         let s_table = meta.complex_selector();
         let s_witness = meta.complex_selector();
 
-        // t_helper(X) * (alpha - t(X)) = 1
+        // t_helper_j(X) * (alpha - t_j(X)) = 1
+        // where t_j(X) = t_j0(X) + beta * t_j1(X) + ...
         meta.create_gate("t-helper", |meta| {
-            let t = meta.query_fixed(t, Rotation(0));
-            let t_helper = meta.query_advice(t_helper, Rotation(0));
+            let beta = meta.query_challenge(beta);
             let alpha = meta.query_challenge(alpha);
-            let identity = t_helper * (alpha - t) - Expression::Constant(F::ONE);
+            let identities = t
+                .iter()
+                .zip(t_helper.iter())
+                .map(|(t, t_helper)| {
+                    let t = t.iter().map(|t| meta.query_any(*t, Rotation(0)));
+                    let t = compress(t.collect::<Vec<_>>().into_iter(), beta.clone());
+                    let t_helper = meta.query_advice(*t_helper, Rotation(0));
+                    t_helper * (alpha.clone() - t) - Expression::Constant(F::ONE)
+                })
+                .collect::<Vec<_>>();
 
             let selector = meta.query_selector(s_table);
-            Constraints::with_selector(selector, std::iter::once(identity))
+            Constraints::with_selector(selector, identities)
         });
 
-        // w_helper(X) * (alpha - w(X)) = 1
+        // w_helper(X) * prod_i(alpha - w_i(X)) = q(X) * sum_i prod_{j != i}(alpha - w_j(X))
+        // for each group of lookups where w(X) = w_0(X) + beta * w_1(X) + ...
+        // and q(X) is boolean, disabled lookups have zero helper.
+        // q(X) = 1 unless the lookup is conditional
         meta.create_gate("w-helper", |meta| {
-            let identities = w
+            let q = meta.query_advice(q, Rotation(0));
+            let conditional = meta.query_fixed(conditional, Rotation(0));
+            let alpha = meta.query_challenge(alpha);
+            let beta = meta.query_challenge(beta);
+            let denominators = w
                 .iter()
+                .map(|w| {
+                    let tag = w_tag.map(|tag| meta.query_fixed(tag, Rotation(0)));
+                    let w = w.iter().map(|w| meta.query_advice(*w, Rotation(0)));
+                    let w = tag.into_iter().chain(w).collect::<Vec<_>>();
+                    let w = compress(w.into_iter(), beta.clone());
+                    alpha.clone() - w
+                })
+                .collect::<Vec<_>>();
+            let identities = denominators
+                .chunks(group_size)
                 .zip(w_helper.iter())
-                .map(|(w, w_helper)| {
-                    let w = meta.query_advice(*w, Rotation(0));
+                .map(|(denominators, w_helper)| {
+                    let product = |skip: Option<usize>| {
+                        denominators
+                            .iter()
+                            .enumerate()
+                            .filter(|(j, _)| Some(*j) != skip)
+                            .map(|(_, d)| d.clone())
+                            .reduce(|acc, d| acc * d)
+                            .unwrap_or(Expression::Constant(F::ONE))
+                    };
+                    let numerator = (0..denominators.len())
+                        .map(|i| product(Some(i)))
+                        .reduce(|acc, p| acc + p)
+                        .unwrap();
                     let w_helper = meta.query_advice(*w_helper, Rotation(0));
-                    let alpha = meta.query_challenge(alpha);
-                    w_helper * (alpha - w) - Expression::Constant(F::ONE)
+                    w_helper * product(None) - q.clone() * numerator
                 })
+                .chain([
+                    q.clone() * (Expression::Constant(F::ONE) - q.clone()),
+                    (Expression::Constant(F::ONE) - conditional)
+                        * (Expression::Constant(F::ONE) - q.clone()),
+                ])
                 .collect::<Vec<_>>();
 
             let selector = meta.query_selector(s_witness);
@@ -78,7 +184,6 @@ impl<F: PrimeField, const W: usize> LogupConfig<F, W> {
 
         // sum(m(x) * t_helper(x) - w_helper(x)) == 0
         meta.create_gate("grand sum", |meta| {
-            let m = meta.query_advice(m, Rotation(0));
             let s_table = meta.query_selector(s_table);
             let s_witness = meta.query_selector(s_witness);
 
@@ -87,10 +192,18 @@ impl<F: PrimeField, const W: usize> LogupConfig<F, W> {
                     .iter()
                     .map(|w_helper| meta.query_advice(*w_helper, Rotation(0)))
                     .sum::<Expression<F>>();
-                let t_helper = meta.query_advice(t_helper, Rotation(0));
+                let t_helper: Expression<F> = m
+                    .iter()
+                    .zip(t_helper.iter())
+                    .map(|(m, t_helper)| {
+                        let m = meta.query_advice(*m, Rotation(0));
+                        let t_helper = meta.query_advice(*t_helper, Rotation(0));
+                        m * t_helper
+                    })
+                    .sum::<Expression<F>>();
 
                 // with the hope that deggree stays at 3
-                s_table * m * t_helper - s_witness * w_helper
+                s_table * t_helper - s_witness * w_helper
             };
             let acc_next = meta.query_advice(acc, Rotation(1));
             let acc = meta.query_advice(acc, Rotation(0));
@@ -108,14 +221,22 @@ impl<F: PrimeField, const W: usize> LogupConfig<F, W> {
             Constraints::with_selector(selector, std::iter::once(identity))
         });
 
+        let degree = gate_degree(meta, n_gates);
+
         Self {
-            w: *w,
-            t,
+            w: w.to_vec(),
+            w_tag,
+            t: t.to_vec(),
             t_helper,
             w_helper,
             m,
+            q,
+            conditional,
             acc,
+            group_size,
+            degree,
             alpha,
+            beta,
 
             s_acc,
             s_zero,
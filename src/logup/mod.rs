@@ -1,2 +1,5 @@
 pub mod assignments;
 pub mod config;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod table;
@@ -0,0 +1,72 @@
+use ff::{BatchInvert, PrimeField};
+use halo2::plonk::Assigned;
+use rayon::prelude::*;
+
+use crate::index::TableIndex;
+
+use super::config::compress;
+
+/// Number of elements inverted together by a thread
+const BATCH_SIZE: usize = 1 << 12;
+
+/// Returns `n_i / d_i` where denominators are inverted in batches across threads. Zero
+/// denominators are kept as rationals so that collisions with alpha are still found.
+fn batch_rationals<F: PrimeField>(numerators: &[F], denominators: Vec<F>) -> Vec<Assigned<F>> {
+    let mut inverses = denominators.clone();
+    inverses.par_chunks_mut(BATCH_SIZE).for_each(|batch| {
+        batch.iter_mut().batch_invert();
+    });
+    numerators
+        .par_iter()
+        .zip(denominators.par_iter())
+        .zip(inverses.par_iter())
+        .map(|((n, d), inv)| match bool::from(d.is_zero()) {
+            true => Assigned::Rational(*n, *d),
+            false => Assigned::Trivial(*n * inv),
+        })
+        .collect()
+}
+
+/// Returns helpers `n_i / (alpha - t_i)` where `t_i` is the compressed `i`th tuple
+pub(crate) fn helpers<F: PrimeField>(
+    alpha: F,
+    beta: F,
+    tuples: &[Vec<F>],
+    numerators: &[F],
+) -> Vec<Assigned<F>> {
+    let denominators = tuples
+        .par_iter()
+        .map(|t| alpha - compress(t.iter().copied(), beta))
+        .collect();
+    batch_rationals(numerators, denominators)
+}
+
+/// Counts enabled lookups of each table row, repeated table entries after the first one get zero
+pub(crate) fn multiplicities<F: PrimeField>(
+    index: &TableIndex,
+    table_size: usize,
+    tuples: &[Vec<F>],
+    enable: &[F],
+) -> Vec<u64> {
+    tuples
+        .par_iter()
+        .zip(enable.par_iter())
+        .fold(
+            || vec![0u64; table_size],
+            |mut counts, (t, q)| {
+                if *q != F::ZERO {
+                    if let Some(i) = index.index(t) {
+                        counts[i] += 1;
+                    }
+                }
+                counts
+            },
+        )
+        .reduce(
+            || vec![0u64; table_size],
+            |mut acc, counts| {
+                acc.iter_mut().zip(counts).for_each(|(acc, m)| *acc += m);
+                acc
+            },
+        )
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use ff::PrimeField;
+use halo2::circuit::Value;
+
+use crate::index::TableIndex;
+
+#[derive(Debug)]
+struct TableData<F: PrimeField> {
+    rows: Vec<Vec<Value<F>>>,
+    index: Option<TableIndex>,
+    arity: usize,
+    tagged: bool,
+}
+
+/// Rows of a logup table and their index computed once. Cloning the handle shares the data so
+/// a prover can build it once and pass it to every gate and every proof, for example through
+/// `Circuit::Params`.
+#[derive(Clone, Debug)]
+pub struct LogupTable<F: PrimeField> {
+    inner: Arc<TableData<F>>,
+}
+
+impl<F: PrimeField> Default for LogupTable<F> {
+    fn default() -> Self {
+        Self::from_rows(Vec::new(), 1, false)
+    }
+}
+
+impl<F: PrimeField> LogupTable<F> {
+    /// Table where each entry of `table` is a row
+    pub fn new(table: Vec<Vec<F>>) -> Self {
+        let arity = table.first().map(|t| t.len()).unwrap_or(1);
+        assert!(table.iter().all(|t| t.len() == arity));
+        let rows = table
+            .into_iter()
+            .map(|t| t.into_iter().map(Value::known).collect())
+            .collect();
+        Self::from_rows(rows, arity, false)
+    }
+
+    /// Single column table
+    pub fn from_values(table: Vec<F>) -> Self {
+        Self::new(table.into_iter().map(|t| vec![t]).collect())
+    }
+
+    /// Many tables that share the same argument. Table `i` is tagged with `i`, tables of
+    /// different arities are padded with zeros.
+    pub fn tagged(tables: Vec<Vec<Vec<F>>>) -> Self {
+        let arity = tables.iter().flatten().map(|t| t.len()).max().unwrap_or(1);
+        let rows = tables
+            .into_iter()
+            .enumerate()
+            .flat_map(|(tag, table)| {
+                table.into_iter().map(move |row| {
+                    std::iter::once(F::from(tag as u64))
+                        .chain(
+                            row.into_iter()
+                                .chain(std::iter::repeat(F::ZERO))
+                                .take(arity),
+                        )
+                        .map(Value::known)
+                        .collect()
+                })
+            })
+            .collect();
+        // tag column comes first
+        Self::from_rows(rows, arity + 1, true)
+    }
+
+    /// Table of rows that may be unknown, rows are indexed only if all of them are known
+    pub(crate) fn from_rows(rows: Vec<Vec<Value<F>>>, arity: usize, tagged: bool) -> Self {
+        assert!(rows.iter().all(|t| t.len() == arity));
+        let known: Value<Vec<Vec<F>>> = rows
+            .iter()
+            .map(|t| t.iter().copied().collect::<Value<Vec<F>>>())
+            .collect();
+        let mut index = None;
+        known.map(|known| index = Some(TableIndex::new(&known)));
+        Self {
+            inner: Arc::new(TableData {
+                rows,
+                index,
+                arity,
+                tagged,
+            }),
+        }
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.inner.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.rows.is_empty()
+    }
+
+    /// Number of columns including the tag column
+    pub fn arity(&self) -> usize {
+        self.inner.arity
+    }
+
+    /// Tag of a table is its first column
+    pub fn is_tagged(&self) -> bool {
+        self.inner.tagged
+    }
+
+    pub(crate) fn rows(&self) -> &[Vec<Value<F>>] {
+        &self.inner.rows
+    }
+
+    pub(crate) fn index(&self) -> Option<&TableIndex> {
+        self.inner.index.as_ref()
+    }
+}
@@ -0,0 +1,78 @@
+use ff::PrimeField;
+use halo2::{
+    circuit::Layouter,
+    plonk::{ConstraintSystem, Error},
+};
+
+use crate::{AssignedValue, EqualityGate, RegionCtx, Witness};
+
+use super::config::PermutationConfig;
+
+#[derive(Clone, Debug)]
+pub struct PermutationGate<F: PrimeField> {
+    cfg: PermutationConfig<F>,
+    rows: Vec<Vec<Witness<F>>>,
+    equalities: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl<F: PrimeField> EqualityGate<F> for PermutationGate<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, width: usize) -> Self {
+        let w = std::iter::repeat_with(|| meta.advice_column())
+            .take(width)
+            .collect::<Vec<_>>();
+
+        let cfg = PermutationConfig::configure(meta, &w);
+
+        Self {
+            cfg,
+            rows: Vec::new(),
+            equalities: Vec::new(),
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.cfg.w.len()
+    }
+
+    fn assign(&mut self, value: &[Witness<F>]) -> usize {
+        assert_eq!(value.len(), self.width());
+        self.rows.push(value.to_vec());
+        self.rows.len() - 1
+    }
+
+    fn equal(&mut self, cell_0: (usize, usize), cell_1: (usize, usize)) {
+        for (row, column) in [cell_0, cell_1] {
+            assert!(row < self.rows.len() && column < self.width());
+        }
+        self.equalities.push((cell_0, cell_1));
+    }
+
+    fn layout(&self, ly: &mut impl Layouter<F>) -> Result<Vec<Vec<AssignedValue<F>>>, Error> {
+        ly.assign_region(
+            || "assign",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+                let mut assigned = Vec::with_capacity(self.rows.len());
+
+                for w in self.rows.iter() {
+                    let row = w
+                        .iter()
+                        .zip(self.cfg.w.iter())
+                        .map(|(w, col)| ctx.witness(*col, w))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    assigned.push(row);
+                    ctx.next();
+                }
+
+                for ((row_0, column_0), (row_1, column_1)) in self.equalities.iter() {
+                    ctx.equal(
+                        assigned[*row_0][*column_0].cell(),
+                        assigned[*row_1][*column_1].cell(),
+                    )?;
+                }
+
+                Ok(assigned)
+            },
+        )
+    }
+}
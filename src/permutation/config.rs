@@ -0,0 +1,21 @@
+use ff::PrimeField;
+use halo2::plonk::{Advice, Column, ConstraintSystem};
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct PermutationConfig<F: PrimeField> {
+    pub(crate) w: Vec<Column<Advice>>,
+    pub(crate) _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PermutationConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, w: &[Column<Advice>]) -> Self {
+        // equalities are handled by the permutation argument of the backend
+        w.iter().for_each(|w| meta.enable_equality(*w));
+
+        PermutationConfig {
+            w: w.to_vec(),
+            _marker: PhantomData,
+        }
+    }
+}
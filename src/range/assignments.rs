@@ -0,0 +1,192 @@
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::{Assigned, ConstraintSystem, Error},
+};
+
+use crate::{
+    error::LookupError, index::le_bytes, range_table, AssignedValue, LookupGate, RegionCtx,
+};
+
+use super::config::RangeConfig;
+
+/// Splits `value` into `n` little endian limbs of `limb_bits`, higher bits are dropped
+pub(crate) fn to_limbs<F: PrimeField>(value: &F, limb_bits: usize, n: usize) -> Vec<u64> {
+    let bytes = le_bytes(value);
+    let bit = |i: usize| bytes.get(i / 8).map_or(0, |byte| (byte >> (i % 8)) & 1) as u64;
+    (0..n)
+        .map(|i| (0..limb_bits).fold(0, |acc, j| acc | (bit(i * limb_bits + j) << j)))
+        .collect()
+}
+
+/// Number of bits of the canonical representation of `value`
+pub(crate) fn bit_length<F: PrimeField>(value: &F) -> usize {
+    let bytes = le_bytes(value);
+    bytes
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |i| 8 * i + 8 - bytes[i].leading_zeros() as usize)
+}
+
+/// Range checks values of arbitrary bit size by decomposing them into limbs that are looked
+/// up in a single `2^limb_bits` range table
+#[derive(Clone, Debug)]
+pub struct RangeChip<F: PrimeField, Gate: LookupGate<F>> {
+    cfg: RangeConfig<F>,
+    gate: Gate,
+    limbs: Vec<AssignedValue<F>>,
+}
+
+impl<F: PrimeField, Gate: LookupGate<F>> RangeChip<F, Gate> {
+    /// Configures the chip with the range table `0..2^limb_bits` where `width` limbs are
+    /// looked up per row
+    pub fn configure(meta: &mut ConstraintSystem<F>, limb_bits: usize, width: usize) -> Self {
+        let cfg = RangeConfig::configure(meta, limb_bits);
+        let gate = Gate::configure(meta, range_table(limb_bits), width);
+        Self {
+            cfg,
+            gate,
+            limbs: Vec::new(),
+        }
+    }
+
+    pub fn limb_bits(&self) -> usize {
+        self.cfg.limb_bits
+    }
+
+    /// Constrains `value` to be less than `2^bits` and returns its limbs in little endian
+    /// order. Top limb has `bits % limb_bits` bits if `bits` is not a multiple of `limb_bits`.
+    pub fn decompose(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        value: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        self.decompose_affine(ly, value, None, bits)
+    }
+
+    /// Decomposes `scale * value + offset` into limbs if `affine` is given
+    fn decompose_affine(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        value: &AssignedValue<F>,
+        affine: Option<(F, F)>,
+        bits: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert!(bits > 0 && bits < F::NUM_BITS as usize);
+        let limb_bits = self.limb_bits();
+        let n = bits.div_ceil(limb_bits);
+        let top_bits = bits - (n - 1) * limb_bits;
+        let shift = F::from(1u64 << (limb_bits - top_bits));
+
+        let x = value.value().map(|x| x.evaluate());
+        let x = match affine {
+            Some((scale, offset)) => x.map(|x| scale * x + offset),
+            None => x,
+        };
+        let limbs = x
+            .map(|x| {
+                to_limbs(&x, limb_bits, n)
+                    .into_iter()
+                    .map(F::from)
+                    .collect()
+            })
+            .transpose_vec(n);
+        // z_{i+1} = (z_i - limb_i) / 2^limb_bits
+        let base_inv = F::from(1u64 << limb_bits).invert().unwrap();
+        let z = std::iter::once(x)
+            .chain(limbs.iter().scan(x, |z, limb| {
+                *z = (*z - *limb) * Value::known(base_inv);
+                Some(*z)
+            }))
+            .collect::<Vec<_>>();
+
+        let (limbs, shifted) = ly.assign_region(
+            || "decompose",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+                let mut assigned = Vec::with_capacity(n);
+
+                ctx.copy(self.cfg.z, value)?;
+                if let Some((scale, offset)) = affine {
+                    ctx.enable(self.cfg.s_input)?;
+                    ctx.fixed(self.cfg.scale, scale)?;
+                    ctx.fixed(self.cfg.offset, offset)?;
+                    ctx.next();
+                    ctx.advice(self.cfg.z, z[0].map(Assigned::from))?;
+                }
+                for (i, limb) in limbs.iter().enumerate() {
+                    ctx.enable(self.cfg.s_sum)?;
+                    if i > 0 {
+                        ctx.advice(self.cfg.z, z[i].map(Assigned::from))?;
+                    }
+                    assigned.push(ctx.advice(self.cfg.limb, limb.map(Assigned::from))?);
+                    ctx.next();
+                }
+
+                ctx.enable(self.cfg.s_last)?;
+                ctx.advice(self.cfg.z, z[n].map(Assigned::from))?;
+                ctx.fixed(self.cfg.shift, shift)?;
+                let shifted = limbs[n - 1] * Value::known(shift);
+                let shifted = ctx.advice(self.cfg.limb, shifted.map(Assigned::from))?;
+
+                Ok((assigned, shifted))
+            },
+        )?;
+
+        self.limbs.extend(limbs.iter().cloned());
+        // top limb is shifted into the table only if it is less than 2^top_bits
+        if top_bits < limb_bits {
+            self.limbs.push(shifted);
+        }
+        Ok(limbs)
+    }
+
+    /// Constrains `value` to be less than `2^bits`
+    pub fn range_check(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        value: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<(), Error> {
+        self.decompose(ly, value, bits).map(|_| ())
+    }
+
+    /// Constrains `lo <= value < hi` by range checking both `value - lo` and `hi - 1 - value`
+    /// to the bit size of `hi - lo - 1`. Bounds are compared as integers and `hi - lo` must be
+    /// small enough that the sum of the two checked values doesn't wrap around the modulus.
+    pub fn range_check_bounded(
+        &mut self,
+        ly: &mut impl Layouter<F>,
+        value: &AssignedValue<F>,
+        lo: F,
+        hi: F,
+    ) -> Result<(), Error> {
+        let bits = std::cmp::max(bit_length(&(hi - lo - F::ONE)), 1);
+        assert!(
+            bits + 1 < F::NUM_BITS as usize,
+            "bounds are too far apart or hi is not greater than lo"
+        );
+        self.decompose_affine(ly, value, Some((F::ONE, -lo)), bits)?;
+        self.decompose_affine(ly, value, Some((-F::ONE, hi - F::ONE)), bits)?;
+        Ok(())
+    }
+
+    /// Lays out the lookups of all limbs in one lookup gate
+    pub fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), LookupError<F>> {
+        let mut gate = self.gate.clone();
+        let width = gate.width();
+        for limbs in self.limbs.chunks(width) {
+            // last row is filled with limbs of the same row
+            let row = limbs
+                .iter()
+                .cycle()
+                .take(width)
+                .cloned()
+                .collect::<Vec<_>>();
+            gate.lookup_assigned(&row);
+        }
+        gate.layout(ly)?;
+        Ok(())
+    }
+}
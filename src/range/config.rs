@@ -0,0 +1,89 @@
+use ff::PrimeField;
+use halo2::{
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct RangeConfig<F: PrimeField> {
+    pub(crate) z: Column<Advice>,
+    pub(crate) limb: Column<Advice>,
+    pub(crate) shift: Column<Fixed>,
+    pub(crate) scale: Column<Fixed>,
+    pub(crate) offset: Column<Fixed>,
+    pub(crate) limb_bits: usize,
+
+    pub(crate) s_input: Selector,
+    pub(crate) s_sum: Selector,
+    pub(crate) s_last: Selector,
+
+    pub(crate) marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> RangeConfig<F> {
+    /// Decomposes `z_0` into little endian limbs of `limb_bits` with the running sum
+    /// `z_i = limb_i + 2^limb_bits * z_{i+1}` that ends with `z_n = 0`. The last row also holds
+    /// the top limb multiplied by `shift` so that a partial top limb can be range checked with
+    /// the same table. Optionally `z_0 = scale * x + offset` where `x` is on the row above.
+    pub fn configure(meta: &mut ConstraintSystem<F>, limb_bits: usize) -> Self {
+        assert!(limb_bits > 0 && limb_bits < 64);
+        let z = meta.advice_column();
+        let limb = meta.advice_column();
+        let shift = meta.fixed_column();
+        let scale = meta.fixed_column();
+        let offset = meta.fixed_column();
+        // decomposed value is copied in and limbs are copied into the lookup gate
+        meta.enable_equality(z);
+        meta.enable_equality(limb);
+
+        let s_input = meta.selector();
+        let s_sum = meta.selector();
+        let s_last = meta.selector();
+
+        // z_0 - scale * x - offset = 0
+        meta.create_gate("input", |meta| {
+            let z_0 = meta.query_advice(z, Rotation(1));
+            let x = meta.query_advice(z, Rotation(0));
+            let scale = meta.query_fixed(scale, Rotation(0));
+            let offset = meta.query_fixed(offset, Rotation(0));
+            let selector = meta.query_selector(s_input);
+            Constraints::with_selector(selector, std::iter::once(z_0 - scale * x - offset))
+        });
+
+        // z_i - limb_i - 2^limb_bits * z_{i+1} = 0
+        meta.create_gate("running sum", |meta| {
+            let z_next = meta.query_advice(z, Rotation(1));
+            let z = meta.query_advice(z, Rotation(0));
+            let limb = meta.query_advice(limb, Rotation(0));
+            let base = Expression::Constant(F::from(1u64 << limb_bits));
+            let selector = meta.query_selector(s_sum);
+            Constraints::with_selector(selector, std::iter::once(z - limb - base * z_next))
+        });
+
+        // z_n = 0 and shifted top limb
+        meta.create_gate("top limb", |meta| {
+            let z = meta.query_advice(z, Rotation(0));
+            let top = meta.query_advice(limb, Rotation(-1));
+            let shifted = meta.query_advice(limb, Rotation(0));
+            let shift = meta.query_fixed(shift, Rotation(0));
+            let selector = meta.query_selector(s_last);
+            Constraints::with_selector(selector, [z, shifted - shift * top])
+        });
+
+        Self {
+            z,
+            limb,
+            shift,
+            scale,
+            offset,
+            limb_bits,
+
+            s_input,
+            s_sum,
+            s_last,
+
+            marker: PhantomData,
+        }
+    }
+}
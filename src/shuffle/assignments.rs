@@ -0,0 +1,180 @@
+use ff::PrimeField;
+use halo2::{
+    circuit::{Layouter, Value},
+    plonk::{Assigned, ConstraintSystem, Error},
+};
+
+use crate::{logup::config::compress, AssignedValue, RegionCtx, Witness};
+
+use super::config::ShuffleConfig;
+
+#[derive(Clone, Debug)]
+pub struct ShuffleGate<F: PrimeField> {
+    cfg: ShuffleConfig<F>,
+    inputs: Vec<Vec<Witness<F>>>,
+    outputs: Vec<Vec<Witness<F>>>,
+}
+
+impl<F: PrimeField> ShuffleGate<F> {
+    /// Configures the gate for rows of `width` values. Rows of inputs are a permutation of rows
+    /// of outputs.
+    pub fn configure(meta: &mut ConstraintSystem<F>, width: usize) -> Self {
+        let a = std::iter::repeat_with(|| meta.advice_column())
+            .take(width)
+            .collect::<Vec<_>>();
+        let b = std::iter::repeat_with(|| meta.advice_column())
+            .take(width)
+            .collect::<Vec<_>>();
+
+        let cfg = ShuffleConfig::configure(meta, &a, &b);
+
+        Self {
+            cfg,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cfg.a.len()
+    }
+
+    /// Adds a row of `width` values to the input multiset
+    pub fn input(&mut self, value: &[Value<F>]) {
+        let value = value
+            .iter()
+            .copied()
+            .map(Witness::Value)
+            .collect::<Vec<_>>();
+        self.input_witness(&value)
+    }
+
+    /// Adds a row of `width` values to the output multiset
+    pub fn output(&mut self, value: &[Value<F>]) {
+        let value = value
+            .iter()
+            .copied()
+            .map(Witness::Value)
+            .collect::<Vec<_>>();
+        self.output_witness(&value)
+    }
+
+    /// Adds a row of `width` values or assigned cells to the input multiset
+    pub fn input_witness(&mut self, value: &[Witness<F>]) {
+        assert_eq!(value.len(), self.width());
+        self.inputs.push(value.to_vec());
+    }
+
+    /// Adds a row of `width` values or assigned cells to the output multiset
+    pub fn output_witness(&mut self, value: &[Witness<F>]) {
+        assert_eq!(value.len(), self.width());
+        self.outputs.push(value.to_vec());
+    }
+
+    /// Lays out the gate and returns cells of inputs and outputs in the order they are added
+    #[allow(clippy::type_complexity)]
+    pub fn layout(
+        &self,
+        ly: &mut impl Layouter<F>,
+    ) -> Result<(Vec<Vec<AssignedValue<F>>>, Vec<Vec<AssignedValue<F>>>), Error> {
+        let alpha: Value<F> = ly.get_challenge(self.cfg.alpha);
+        let beta: Value<F> = ly.get_challenge(self.cfg.beta);
+
+        // helper = 1 / (alpha - w) where w is the compressed row
+        let helpers = |rows: &[Vec<Witness<F>>]| {
+            rows.iter()
+                .map(|row| {
+                    let w = compress(row.iter().map(|w| w.value()), beta);
+                    (alpha - w).map(|inv| Assigned::Rational(F::ONE, inv))
+                })
+                .collect::<Vec<_>>()
+        };
+        let a_helper = helpers(&self.inputs);
+        let b_helper = helpers(&self.outputs);
+
+        ly.assign_region(
+            || "shuffle",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+                let mut inputs = Vec::with_capacity(self.inputs.len());
+                let mut outputs = Vec::with_capacity(self.outputs.len());
+
+                let n = std::cmp::max(self.inputs.len(), self.outputs.len());
+
+                // init acc to zero
+                let mut acc: Value<Assigned<F>> = Value::known(F::ZERO).into();
+                ctx.enable(self.cfg.s_zero)?;
+
+                let a = self
+                    .inputs
+                    .iter()
+                    .zip(a_helper.iter())
+                    .map(Some)
+                    .chain(std::iter::repeat(None))
+                    .take(n);
+                let b = self
+                    .outputs
+                    .iter()
+                    .zip(b_helper.iter())
+                    .map(Some)
+                    .chain(std::iter::repeat(None))
+                    .take(n);
+
+                for (a, b) in a.zip(b) {
+                    ctx.enable(self.cfg.s_acc)?;
+                    ctx.advice(self.cfg.acc, acc)?;
+
+                    match a {
+                        Some((w, h)) => {
+                            ctx.enable(self.cfg.s_a)?;
+                            let row = self
+                                .cfg
+                                .a
+                                .iter()
+                                .zip(w.iter())
+                                .map(|(column, w)| ctx.witness(*column, w))
+                                .collect::<Result<Vec<_>, Error>>()?;
+                            inputs.push(row);
+                            ctx.advice(self.cfg.a_helper, *h)?;
+                            acc = acc + h;
+                        }
+                        None => {
+                            for column in self.cfg.a.iter() {
+                                ctx.empty((*column).into())?;
+                            }
+                            ctx.empty(self.cfg.a_helper.into())?;
+                        }
+                    }
+
+                    match b {
+                        Some((w, h)) => {
+                            ctx.enable(self.cfg.s_b)?;
+                            let row = self
+                                .cfg
+                                .b
+                                .iter()
+                                .zip(w.iter())
+                                .map(|(column, w)| ctx.witness(*column, w))
+                                .collect::<Result<Vec<_>, Error>>()?;
+                            outputs.push(row);
+                            ctx.advice(self.cfg.b_helper, *h)?;
+                            acc = acc - h;
+                        }
+                        None => {
+                            for column in self.cfg.b.iter() {
+                                ctx.empty((*column).into())?;
+                            }
+                            ctx.empty(self.cfg.b_helper.into())?;
+                        }
+                    }
+                    ctx.next();
+                }
+
+                ctx.advice(self.cfg.acc, acc)?;
+                ctx.enable(self.cfg.s_zero)?;
+
+                Ok((inputs, outputs))
+            },
+        )
+    }
+}
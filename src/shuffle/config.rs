@@ -0,0 +1,127 @@
+use ff::PrimeField;
+use halo2::{
+    plonk::{
+        Advice, Challenge, Column, ConstraintSystem, Constraints, Expression, FirstPhase,
+        SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::logup::config::compress;
+
+#[derive(Clone, Debug)]
+pub struct ShuffleConfig<F: PrimeField> {
+    pub(crate) a: Vec<Column<Advice>>,
+    pub(crate) b: Vec<Column<Advice>>,
+    pub(crate) a_helper: Column<Advice>,
+    pub(crate) b_helper: Column<Advice>,
+    pub(crate) acc: Column<Advice>,
+    pub(crate) alpha: Challenge,
+    pub(crate) beta: Challenge,
+
+    pub(crate) s_zero: Selector,
+    pub(crate) s_acc: Selector,
+
+    pub(crate) s_a: Selector,
+    pub(crate) s_b: Selector,
+
+    pub(crate) marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ShuffleConfig<F> {
+    /// Rows of `a` columns are a permutation of rows of `b` columns.
+    /// Rows are compressed into single values with `beta` and then it is the same as logup
+    /// argument where each row of `b` is a table entry with multiplicity one.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a: &[Column<Advice>],
+        b: &[Column<Advice>],
+    ) -> Self {
+        assert!(!a.is_empty());
+        assert_eq!(a.len(), b.len());
+        // shuffled values can be copied from other regions
+        a.iter()
+            .chain(b.iter())
+            .for_each(|w| meta.enable_equality(*w));
+
+        let a_helper = meta.advice_column_in(SecondPhase);
+        let b_helper = meta.advice_column_in(SecondPhase);
+        let acc = meta.advice_column_in(SecondPhase);
+
+        let alpha = meta.challenge_usable_after(FirstPhase);
+        // row compression
+        let beta = meta.challenge_usable_after(FirstPhase);
+
+        let s_zero = meta.selector();
+        let s_acc = meta.selector();
+
+        let s_a = meta.complex_selector();
+        let s_b = meta.complex_selector();
+
+        // helper(X) * (alpha - w(X)) = 1
+        // where w(X) = w_0(X) + beta * w_1(X) + ...
+        let helper_gate = |meta: &mut ConstraintSystem<F>,
+                           name: &'static str,
+                           w: &[Column<Advice>],
+                           helper: Column<Advice>,
+                           selector: Selector| {
+            meta.create_gate(name, |meta| {
+                let alpha = meta.query_challenge(alpha);
+                let beta = meta.query_challenge(beta);
+                let w = w
+                    .iter()
+                    .map(|w| meta.query_advice(*w, Rotation(0)))
+                    .collect::<Vec<_>>();
+                let w = compress(w.into_iter(), beta);
+                let helper = meta.query_advice(helper, Rotation(0));
+                let identity = helper * (alpha - w) - Expression::Constant(F::ONE);
+                let selector = meta.query_selector(selector);
+                Constraints::with_selector(selector, std::iter::once(identity))
+            });
+        };
+        helper_gate(meta, "a-helper", a, a_helper, s_a);
+        helper_gate(meta, "b-helper", b, b_helper, s_b);
+
+        // sum(a_helper(x) - b_helper(x)) == 0
+        meta.create_gate("grand sum", |meta| {
+            let s_a = meta.query_selector(s_a);
+            let s_b = meta.query_selector(s_b);
+            let a_helper = meta.query_advice(a_helper, Rotation(0));
+            let b_helper = meta.query_advice(b_helper, Rotation(0));
+            let contrib = s_a * a_helper - s_b * b_helper;
+
+            let acc_next = meta.query_advice(acc, Rotation(1));
+            let acc = meta.query_advice(acc, Rotation(0));
+            let identity = contrib + acc - acc_next;
+
+            let selector = meta.query_selector(s_acc);
+            Constraints::with_selector(selector, std::iter::once(identity))
+        });
+
+        // zero sum check
+        meta.create_gate("zero acc", |meta| {
+            let acc = meta.query_advice(acc, Rotation(0));
+            let selector = meta.query_selector(s_zero);
+            Constraints::with_selector(selector, std::iter::once(acc))
+        });
+
+        Self {
+            a: a.to_vec(),
+            b: b.to_vec(),
+            a_helper,
+            b_helper,
+            acc,
+            alpha,
+            beta,
+
+            s_zero,
+            s_acc,
+
+            s_a,
+            s_b,
+
+            marker: PhantomData,
+        }
+    }
+}
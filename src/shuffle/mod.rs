@@ -0,0 +1,2 @@
+pub mod assignments;
+pub mod config;
@@ -4,45 +4,116 @@ use halo2::{
     plonk::{ConstraintSystem, Error},
 };
 
-use crate::{LookupGate, RegionCtx};
+use crate::{
+    error::LookupError,
+    index::TableIndex,
+    info::{histogram, GateInfo},
+    AssignedValue, LookupGate, RegionCtx, Witness,
+};
 
 use super::config::SubsetConfig;
 
 #[derive(Clone, Debug)]
-pub struct SubsetGate<F: PrimeField + Ord, const W: usize> {
-    cfg: SubsetConfig<F, W>,
-    bit_size: usize,
-    witnesses: Vec<[Value<F>; W]>,
+pub struct SubsetGate<F: PrimeField> {
+    cfg: SubsetConfig<F>,
+    table: Vec<F>,
+    index: TableIndex,
+    witnesses: Vec<Vec<Witness<F>>>,
+}
+
+impl<F: PrimeField> SubsetGate<F> {
+    /// Checks that every known lookup is in the table
+    pub fn check_witnesses(&self) -> Result<(), LookupError<F>> {
+        self.witnesses
+            .iter()
+            .enumerate()
+            .try_for_each(|(lookup, row)| {
+                row.iter().enumerate().try_for_each(|(column, w)| {
+                    let mut result = Ok(());
+                    w.value().map(|value| {
+                        if self.index.index(&[value]).is_none() {
+                            result = Err(LookupError::NotInTable {
+                                lookup,
+                                column,
+                                value: vec![value],
+                            });
+                        }
+                    });
+                    result
+                })
+            })
+    }
 }
 
-impl<F: PrimeField + Ord, const W: usize> LookupGate<F, W> for SubsetGate<F, W> {
-    fn configure(meta: &mut ConstraintSystem<F>, bit_size: usize) -> Self {
+impl<F: PrimeField> LookupGate<F> for SubsetGate<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, table: Vec<F>, width: usize) -> Self {
         let w = std::iter::repeat_with(|| meta.advice_column())
-            .take(W)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+            .take(width)
+            .collect::<Vec<_>>();
 
         let cfg = SubsetConfig::configure(meta, &w);
+        let index = TableIndex::new(&table.iter().map(|t| vec![*t]).collect::<Vec<_>>());
 
         Self {
             cfg,
-            bit_size,
+            table,
+            index,
             witnesses: Vec::new(),
         }
     }
 
-    fn lookup(&mut self, value: &[Value<F>; W]) {
-        self.witnesses.push(*value);
+    fn width(&self) -> usize {
+        self.cfg.w.len()
+    }
+
+    fn lookup(&mut self, value: &[Value<F>]) {
+        assert_eq!(value.len(), self.width());
+        self.witnesses
+            .push(value.iter().copied().map(Witness::Value).collect());
+    }
+
+    fn lookup_assigned(&mut self, value: &[AssignedValue<F>]) {
+        assert_eq!(value.len(), self.width());
+        self.witnesses
+            .push(value.iter().cloned().map(Witness::Assigned).collect());
     }
 
-    fn layout(&self, ly: &mut impl Layouter<F>) -> Result<(), Error> {
+    fn info(&self) -> GateInfo {
+        // repeated table entries after the first one get zero
+        let mut multiplicities = vec![0; self.table.len()];
+        self.witnesses.iter().flatten().for_each(|w| {
+            w.value().map(|value| {
+                if let Some(i) = self.index.index(&[value]) {
+                    multiplicities[i] += 1;
+                }
+            });
+        });
+        GateInfo {
+            name: "subset",
+            advice: vec![self.width()],
+            // table column
+            fixed: 1,
+            selectors: 1,
+            rows: std::cmp::max(self.witnesses.len(), self.table.len()),
+            degree: self.cfg.degree,
+            table_size: self.table.len(),
+            lookups: self.witnesses.len() * self.width(),
+            multiplicities: histogram(multiplicities.into_iter()),
+        }
+    }
+
+    fn layout(
+        &self,
+        ly: &mut impl Layouter<F>,
+    ) -> Result<Vec<Vec<AssignedValue<F>>>, LookupError<F>> {
+        #[cfg(feature = "synth-sanity")]
+        self.check_witnesses()?;
+
         // layout table
         ly.assign_table(
             || "",
             |mut table| {
-                let table_values: Vec<F> = (0..1 << self.bit_size).map(|e| F::from(e)).collect();
-                for (offset, value) in table_values.iter().enumerate() {
+                for (offset, value) in self.table.iter().enumerate() {
                     table.assign_cell(
                         || "table value",
                         self.cfg.t,
@@ -56,23 +127,30 @@ impl<F: PrimeField + Ord, const W: usize> LookupGate<F, W> for SubsetGate<F, W>
 
         // latout witnesses
 
-        ly.assign_region(
+        let assigned = ly.assign_region(
             || "assign",
             |region| {
                 let mut ctx = RegionCtx::new(region);
+                let mut assigned = Vec::with_capacity(self.witnesses.len());
 
                 for w in self.witnesses.iter() {
-                    for (w, col) in w.iter().zip(self.cfg.w) {
-                        ctx.advice(col, w.map(|w| w.into()))?;
-                    }
+                    let row = w
+                        .iter()
+                        .zip(self.cfg.w.iter())
+                        .map(|(w, col)| ctx.witness(*col, w))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    assigned.push(row);
                     ctx.enable(self.cfg.s)?;
                     ctx.next();
                 }
 
-                Ok(())
+                Ok(assigned)
             },
         )?;
 
-        Ok(())
+        #[cfg(feature = "info")]
+        println!("{}", self.info());
+
+        Ok(assigned)
     }
 }
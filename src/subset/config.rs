@@ -9,30 +9,37 @@ use halo2::{
 use std::marker::PhantomData;
 
 #[derive(Clone, Debug)]
-pub struct SubsetConfig<F: PrimeField, const W: usize> {
-    pub(crate) w: [Column<Advice>; W],
+pub struct SubsetConfig<F: PrimeField> {
+    pub(crate) w: Vec<Column<Advice>>,
     pub(crate) t: TableColumn,
     pub(crate) s: Selector,
+    pub(crate) degree: usize,
     pub(crate) _marker: PhantomData<F>,
 }
 
-impl<F: PrimeField, const W: usize> SubsetConfig<F, W> {
-    pub fn configure(meta: &mut ConstraintSystem<F>, w: &[Column<Advice>; W]) -> Self {
+impl<F: PrimeField> SubsetConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, w: &[Column<Advice>]) -> Self {
         let t = meta.lookup_table_column();
         let s = meta.complex_selector();
+        // looked up values can be copied from other regions
+        w.iter().for_each(|w| meta.enable_equality(*w));
 
+        let mut degree = 0;
         for w in w.iter() {
             meta.lookup("lookup", |meta| {
                 let w = meta.query_advice(*w, Rotation(0));
                 let s = meta.query_selector(s);
+                // same as the required degree of halo2 lookup argument with a single table column
+                degree = std::cmp::max(4, 2 + (s.clone() * w.clone()).degree() + 1);
                 vec![(s * w, t)]
             });
         }
 
         SubsetConfig {
-            w: *w,
+            w: w.to_vec(),
             t,
             s,
+            degree,
             _marker: PhantomData,
         }
     }
@@ -1,54 +1,67 @@
+use crate::bitwise::assignments::BitwiseChip;
+use crate::comparison::assignments::ComparisonChip;
+use crate::copy::assignments::CopyGate;
+use crate::error::{Identity, LookupError};
+use crate::index::TableIndex;
 use crate::logup::assignments::LogupGate;
+use crate::logup::config::LogupConfig;
+use crate::logup::table::LogupTable;
+use crate::permutation::assignments::PermutationGate;
+use crate::range::assignments::RangeChip;
+use crate::shuffle::assignments::ShuffleGate;
 use crate::subset::assignments::SubsetGate;
-use crate::LookupGate;
+use crate::{range_table, AssignedValue, EqualityGate, LookupGate, RegionCtx, Witness};
 use core::num;
-use ff::{FromUniformBytes, PrimeField};
+use ff::{Field, FromUniformBytes, PrimeField};
 use halo2::circuit::{SimpleFloorPlanner, Value};
 use halo2::dev::MockProver;
+use halo2::halo2curves::bn256::Fr;
 use halo2::{
     circuit::Layouter,
     plonk::Error,
-    plonk::{Circuit, ConstraintSystem},
+    plonk::{Advice, Any, Circuit, Column, ConstraintSystem},
 };
+use rand::seq::SliceRandom;
 use rand::Rng;
 use rand_core::OsRng;
 use std::marker::PhantomData;
 
 #[derive(Default, Clone, Debug)]
-struct Params {
-    bit_size: usize,
+struct Params<F> {
+    table: Vec<F>,
+    width: usize,
 }
 
 #[derive(Clone, Debug)]
-struct TestConfig<F: PrimeField + Ord, Gate: LookupGate<F, W>, const W: usize> {
+struct TestConfig<F: PrimeField, Gate: LookupGate<F>> {
     gate: Gate,
     _marker: PhantomData<F>,
 }
 
 #[derive(Debug, Default)]
-struct TestCircuit<F: PrimeField + Ord, Gate: LookupGate<F, W>, const W: usize> {
+struct TestCircuit<F: PrimeField, Gate: LookupGate<F>> {
     _marker: PhantomData<(F, Gate)>,
-    bit_size: usize,
+    table: Vec<F>,
+    width: usize,
     lookups_per_column: usize,
 }
 
-impl<F: PrimeField + Ord, Gate: LookupGate<F, W>, const W: usize> Circuit<F>
-    for TestCircuit<F, Gate, W>
-{
-    type Config = TestConfig<F, Gate, W>;
+impl<F: PrimeField, Gate: LookupGate<F>> Circuit<F> for TestCircuit<F, Gate> {
+    type Config = TestConfig<F, Gate>;
     type FloorPlanner = SimpleFloorPlanner;
-    type Params = Params;
+    type Params = Params<F>;
 
     fn without_witnesses(&self) -> Self {
         Self {
-            bit_size: self.bit_size,
+            table: self.table.clone(),
+            width: self.width,
             lookups_per_column: self.lookups_per_column,
             _marker: PhantomData,
         }
     }
 
     fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
-        let gate = Gate::configure(meta, params.bit_size);
+        let gate = Gate::configure(meta, params.table, params.width);
         TestConfig {
             gate,
             _marker: PhantomData,
@@ -60,20 +73,15 @@ impl<F: PrimeField + Ord, Gate: LookupGate<F, W>, const W: usize> Circuit<F>
     }
 
     fn synthesize(&self, mut cfg: Self::Config, mut ly: impl Layouter<F>) -> Result<(), Error> {
-        let table_size = 1 << self.bit_size;
-
         let w = (0..self.lookups_per_column as u64)
             .map(|_| {
                 //
-                let w: [Value<F>; W] = std::iter::repeat_with(|| {
-                    let w = OsRng.gen_range(0..table_size as u64);
-                    let w = F::from(w);
+                let w: Vec<Value<F>> = std::iter::repeat_with(|| {
+                    let w = self.table[OsRng.gen_range(0..self.table.len())];
                     Value::known(w)
                 })
-                .take(W)
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap();
+                .take(self.width)
+                .collect::<Vec<_>>();
                 w
             })
             .collect::<Vec<_>>();
@@ -87,19 +95,22 @@ impl<F: PrimeField + Ord, Gate: LookupGate<F, W>, const W: usize> Circuit<F>
 
     fn params(&self) -> Self::Params {
         Params {
-            bit_size: self.bit_size,
+            table: self.table.clone(),
+            width: self.width,
         }
     }
 }
 
-fn run_test_lookup<F: FromUniformBytes<64> + Ord, Gate: LookupGate<F, W>, const W: usize>(
+fn run_test_lookup<F: FromUniformBytes<64>, Gate: LookupGate<F>>(
     k: u32,
-    bit_size: usize,
+    table: Vec<F>,
+    width: usize,
     lookups_per_column: usize,
 ) {
-    let circuit = TestCircuit::<F, Gate, W> {
+    let circuit = TestCircuit::<F, Gate> {
         _marker: PhantomData,
-        bit_size,
+        table,
+        width,
         lookups_per_column,
     };
     let public_inputs = vec![];
@@ -112,90 +123,1907 @@ fn run_test_lookup<F: FromUniformBytes<64> + Ord, Gate: LookupGate<F, W>, const
 
 #[test]
 fn test_lookup() {
-    use halo2::halo2curves::bn256::Fr;
-    run_test_lookup::<Fr, LogupGate<Fr, 10>, 10>(10, 5, 1 << 6);
-    run_test_lookup::<Fr, LogupGate<Fr, 10>, 10>(10, 5, 1 << 3);
-    run_test_lookup::<Fr, SubsetGate<Fr, 10>, 10>(10, 5, 1 << 6);
-    run_test_lookup::<Fr, SubsetGate<Fr, 10>, 10>(10, 5, 1 << 3);
+    let table = range_table::<Fr>(5);
+    for width in [1, 10] {
+        run_test_lookup::<Fr, LogupGate<Fr>>(10, table.clone(), width, 1 << 6);
+        run_test_lookup::<Fr, LogupGate<Fr>>(10, table.clone(), width, 1 << 3);
+        run_test_lookup::<Fr, SubsetGate<Fr>>(10, table.clone(), width, 1 << 6);
+        run_test_lookup::<Fr, SubsetGate<Fr>>(10, table.clone(), width, 1 << 3);
+    }
 }
 
-mod prover {
+#[test]
+fn test_lookup_arbitrary_table() {
+    // spread table, bits of the value interleaved with zeros
+    let spread = |v: u64| (0..8).fold(0u64, |acc, i| acc | (((v >> i) & 1) << (2 * i)));
+    let table = (0..1 << 8).map(|v| Fr::from(spread(v))).collect::<Vec<_>>();
+    run_test_lookup::<Fr, LogupGate<Fr>>(10, table.clone(), 4, 1 << 6);
+    run_test_lookup::<Fr, SubsetGate<Fr>>(10, table, 4, 1 << 6);
 
-    use std::marker::PhantomData;
+    // random values with repeated entries
+    let mut table = (0..20).map(|_| Fr::random(OsRng)).collect::<Vec<_>>();
+    table.extend_from_within(..10);
+    run_test_lookup::<Fr, LogupGate<Fr>>(10, table.clone(), 4, 1 << 6);
+    run_test_lookup::<Fr, SubsetGate<Fr>>(10, table, 4, 1 << 6);
+}
 
-    use ark_std::{end_timer, start_timer};
-    use halo2::halo2curves::bn256::{Bn256, Fr};
-    use halo2::plonk::{create_proof, keygen_pk, keygen_vk};
-    use halo2::poly::commitment::ParamsProver;
-    use halo2::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
-    use halo2::poly::kzg::multiopen::ProverSHPLONK;
-    use halo2::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
-    use rand_core::OsRng;
+#[test]
+fn test_out_of_table_witness() {
+    let w = |values: [u64; 2]| values.map(|v| Value::known(Fr::from(v)));
+    let is_out_of_table = |result: Result<(), LookupError<Fr>>| match result {
+        Err(LookupError::NotInTable {
+            lookup,
+            column,
+            value,
+        }) => lookup == 1 && column == 1 && value == vec![Fr::from(16u64)],
+        _ => false,
+    };
 
-    use crate::logup::assignments::LogupGate;
-    use crate::subset::assignments::SubsetGate;
-    use crate::LookupGate;
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let mut gate = LogupGate::configure(&mut meta, range_table(4), 2);
+    gate.lookup(&w([3, 5]));
+    gate.lookup(&w([3, 16]));
+    assert!(is_out_of_table(gate.check_witnesses()));
 
-    use super::TestCircuit;
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let mut gate = SubsetGate::configure(&mut meta, range_table(4), 2);
+    gate.lookup(&w([3, 5]));
+    gate.lookup(&w([3, 16]));
+    assert!(is_out_of_table(gate.check_witnesses()));
+}
 
-    fn run_bench_prover<Gate: LookupGate<Fr, W>, const W: usize>(
-        desc: &str,
-        k: u32,
-        bit_size: usize,
-        lookups_per_column: usize,
-    ) {
-        let circuit = TestCircuit::<Fr, Gate, W> {
+#[test]
+fn test_table_index() {
+    let rows = |values: &[u64]| {
+        values
+            .iter()
+            .map(|v| vec![Fr::from(*v)])
+            .collect::<Vec<_>>()
+    };
+
+    let index = TableIndex::new(&rows(&[0, 1, 2, 3]));
+    assert!(matches!(index, TableIndex::Range(4)));
+    assert_eq!(index.index(&[Fr::from(2u64)]), Some(2));
+    assert_eq!(index.index(&[Fr::from(4u64)]), None);
+    assert_eq!(index.index(&[-Fr::ONE]), None);
+
+    // repeated rows map to their first occurrence
+    let index = TableIndex::new(&rows(&[7, 3, 7, 5]));
+    assert!(matches!(index, TableIndex::Hash(_)));
+    assert_eq!(index.index(&[Fr::from(7u64)]), Some(0));
+    assert_eq!(index.index(&[Fr::from(5u64)]), Some(3));
+    assert_eq!(index.index(&[Fr::from(4u64)]), None);
+
+    let tuples = vec![
+        vec![Fr::from(1u64), Fr::from(2u64)],
+        vec![Fr::from(2u64), Fr::from(1u64)],
+    ];
+    let index = TableIndex::new(&tuples);
+    assert_eq!(index.index(&[Fr::from(2u64), Fr::from(1u64)]), Some(1));
+    assert_eq!(index.index(&[Fr::from(2u64), Fr::from(2u64)]), None);
+}
+
+#[test]
+fn test_info() {
+    fn run<Gate: LookupGate<Fr>>(advice: Vec<usize>, rows: usize) {
+        let mut meta = ConstraintSystem::<Fr>::default();
+        let mut gate = Gate::configure(&mut meta, range_table(4), 2);
+        for w in [[3, 5], [3, 7], [1, 3]] {
+            gate.lookup(&w.map(|v: u64| Value::known(Fr::from(v))));
+        }
+        let info = gate.info();
+        assert_eq!(info.advice, advice);
+        assert_eq!(info.rows, rows);
+        assert_eq!(info.table_size, 16);
+        assert_eq!(info.lookups, 6);
+        assert_eq!(
+            info.multiplicities.into_iter().collect::<Vec<_>>(),
+            vec![(0, 12), (1, 3), (3, 1)]
+        );
+    }
+    run::<LogupGate<Fr>>(vec![4, 4], 17);
+    run::<SubsetGate<Fr>>(vec![2], 16);
+}
+
+#[derive(Clone, Debug, Default)]
+struct OutOfTableCircuit;
+
+impl Circuit<Fr> for OutOfTableCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        LogupGate::configure(meta, range_table(4), 2)
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        gate.lookup(&[Value::known(Fr::from(3u64)), Value::known(Fr::from(16u64))]);
+        let result = gate.layout(&mut ly);
+        // witnesses are checked against the table under synth-sanity and the grand sum is
+        // checked under prover-sanity, otherwise only the constraints fail
+        if cfg!(feature = "synth-sanity") {
+            assert!(matches!(
+                &result,
+                Err(LookupError::NotInTable { lookup: 0, column: 1, value })
+                    if *value == vec![Fr::from(16u64)]
+            ));
+        } else if cfg!(feature = "prover-sanity") {
+            assert!(matches!(
+                result,
+                Err(LookupError::Identity(Identity::GrandSum))
+            ));
+        }
+        result?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_out_of_table_layout() {
+    // layout returns an error before the constraint system is checked if sanity checks are on
+    let satisfied = match MockProver::run(6, &OutOfTableCircuit, vec![]) {
+        Ok(prover) => prover.verify().is_ok(),
+        Err(_) => false,
+    };
+    assert!(!satisfied);
+}
+
+#[test]
+fn test_alpha_collision() {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let mut gate = LogupGate::configure(&mut meta, range_table(4), 2);
+    gate.lookup(&[Value::known(Fr::from(3u64)), Value::known(Fr::from(5u64))]);
+    let table = gate.padded_table();
+    let beta = Value::known(Fr::random(OsRng));
+
+    let alpha = Value::known(Fr::random(OsRng));
+    let (_, t_helper, w_inv) = gate.helpers(&table, alpha, beta);
+    assert!(gate.check_collisions(&t_helper, &w_inv).is_ok());
+
+    // alpha is a table entry
+    let alpha = Value::known(Fr::from(7u64));
+    let (_, t_helper, w_inv) = gate.helpers(&table, alpha, beta);
+    assert!(matches!(
+        gate.check_collisions(&t_helper, &w_inv),
+        Err(LookupError::AlphaCollision(Identity::TableHelper {
+            row: 7
+        }))
+    ));
+
+    // alpha is a looked up value
+    let alpha = Value::known(Fr::from(5u64));
+    let (_, _, w_inv) = gate.helpers(&table, alpha, beta);
+    assert!(matches!(
+        gate.check_collisions(&[], &w_inv),
+        Err(LookupError::AlphaCollision(Identity::WitnessHelper {
+            lookup: 0,
+            column: 1
+        }))
+    ));
+}
+
+#[derive(Clone, Debug, Default)]
+struct CapacityCircuit {
+    k: u32,
+    bit_size: usize,
+    set_k: bool,
+}
+
+impl Circuit<Fr> for CapacityCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<Fr>, bit_size: usize) -> Self::Config {
+        LogupGate::configure(meta, range_table(bit_size), 1)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        if self.set_k {
+            gate.set_k(self.k);
+        }
+        gate.lookup(&[Value::known(Fr::ONE)]);
+        let required_k = gate.required_k();
+        let result = gate.layout(&mut ly);
+        // without `set_k` the error is returned once the region runs out of rows
+        if self.k < required_k {
+            assert!(matches!(
+                result,
+                Err(LookupError::NotEnoughRows { required, k })
+                    if required == (1 << self.bit_size) + 1 && k == self.k
+            ));
+        }
+        result?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        self.bit_size
+    }
+}
+
+#[test]
+fn test_capacity() {
+    // table rows and the last accumulator row against 2^k - 6 usable rows
+    assert_eq!(LogupGate::<Fr>::min_k(249, 1, 249, 1, None), 8);
+    assert_eq!(LogupGate::<Fr>::min_k(250, 1, 250, 1, None), 9);
+    assert_eq!(LogupGate::<Fr>::min_k(16, 4, 996, 1, None), 8);
+    // last accumulator row of each region
+    assert_eq!(LogupGate::<Fr>::min_k(16, 4, 992, 1, Some(124)), 8);
+    assert_eq!(LogupGate::<Fr>::min_k(16, 4, 992, 1, Some(31)), 9);
+    // rows of each set of table columns
+    assert_eq!(LogupGate::<Fr>::min_k(498, 1, 1, 2, None), 8);
+    assert_eq!(LogupGate::<Fr>::min_k(498, 1, 1, 1, None), 9);
+
+    assert_eq!(LogupGate::<Fr>::table_sets_for_k(1 << 8, 8), 2);
+    assert_eq!(LogupGate::<Fr>::table_sets_for_k(1 << 8, 9), 1);
+
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let mut gate = LogupGate::configure(&mut meta, range_table(8), 1);
+    gate.lookup(&[Value::known(Fr::ONE)]);
+    let k = gate.required_k();
+    assert_eq!(k, 9);
+
+    for set_k in [true, false] {
+        let circuit = CapacityCircuit {
+            k,
+            bit_size: 8,
+            set_k,
+        };
+        MockProver::run(k, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+
+        let circuit = CapacityCircuit {
+            k: k - 1,
+            bit_size: 8,
+            set_k,
+        };
+        assert!(MockProver::run(k - 1, &circuit, vec![]).is_err());
+    }
+}
+
+#[test]
+#[should_panic(expected = "no rows left for the table")]
+fn test_table_sets_for_small_k() {
+    LogupGate::<Fr>::table_sets_for_k(16, 2);
+}
+
+/// Assigns `values` down `column` in a region of their own, stands for cells that are computed
+/// somewhere else in the circuit
+fn assign_column(
+    ly: &mut impl Layouter<Fr>,
+    column: Column<Advice>,
+    values: &[Value<Fr>],
+) -> Result<Vec<AssignedValue<Fr>>, Error> {
+    ly.assign_region(
+        || "values",
+        |region| {
+            let mut ctx = RegionCtx::new(region);
+            values
+                .iter()
+                .map(|value| {
+                    let cell = ctx.advice(column, value.map(|value| value.into()));
+                    ctx.next();
+                    cell
+                })
+                .collect()
+        },
+    )
+}
+
+/// Constrains each cell of `cells` to be equal to the cell of `expected` at the same index
+fn expect_equal(
+    ly: &mut impl Layouter<Fr>,
+    cells: &[AssignedValue<Fr>],
+    expected: &[AssignedValue<Fr>],
+) -> Result<(), Error> {
+    assert_eq!(cells.len(), expected.len());
+    ly.assign_region(
+        || "expect equal",
+        |region| {
+            let mut ctx = RegionCtx::new(region);
+            for (cell, expected) in cells.iter().zip(expected.iter()) {
+                ctx.equal(cell.cell(), expected.cell())?;
+            }
+            Ok(())
+        },
+    )
+}
+
+#[derive(Debug, Default)]
+struct AssignedCircuit<Gate: LookupGate<Fr>> {
+    _marker: PhantomData<Gate>,
+    table: Vec<Fr>,
+    width: usize,
+    lookups_per_column: usize,
+}
+
+impl<Gate: LookupGate<Fr>> Circuit<Fr> for AssignedCircuit<Gate> {
+    type Config = (Gate, Column<Advice>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = Params<Fr>;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
             _marker: PhantomData,
-            bit_size,
-            lookups_per_column,
+            table: self.table.clone(),
+            width: self.width,
+            lookups_per_column: self.lookups_per_column,
+        }
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        params: Self::Params,
+    ) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (Gate::configure(meta, params.table, params.width), a)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let (mut gate, a) = cfg;
+
+        let values = (0..self.lookups_per_column * self.width)
+            .map(|_| Value::known(self.table[OsRng.gen_range(0..self.table.len())]))
+            .collect::<Vec<_>>();
+        let cells = assign_column(&mut ly, a, &values)?;
+
+        cells
+            .chunks(self.width)
+            .for_each(|w| gate.lookup_assigned(w));
+        let assigned = gate.layout(&mut ly)?;
+
+        // cells returned by the gate can be used further
+        expect_equal(&mut ly, &cells, &assigned.concat())
+    }
+
+    fn params(&self) -> Self::Params {
+        Params {
+            table: self.table.clone(),
+            width: self.width,
+        }
+    }
+}
+
+#[test]
+fn test_lookup_assigned() {
+    fn run<Gate: LookupGate<Fr>>() {
+        let circuit = AssignedCircuit::<Gate> {
+            _marker: PhantomData,
+            table: range_table(5),
+            width: 4,
+            lookups_per_column: 1 << 5,
         };
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+    run::<LogupGate<Fr>>();
+    run::<SubsetGate<Fr>>();
+}
 
-        let params = read_srs(k);
-        let vk = keygen_vk(&params, &circuit).unwrap();
-        let pk = keygen_pk(&params, vk, &circuit).unwrap();
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+#[derive(Clone, Debug, Default)]
+struct XorCircuit {
+    bit_size: usize,
+    width: usize,
+    lookups_per_column: usize,
+}
 
-        let desc = format!(
-            "{desc}, k: {k}, W: {W}, b: {bit_size}, l: {lookups_per_column}, n: {}",
-            lookups_per_column * W
-        );
+fn xor_table<F: PrimeField>(bit_size: usize) -> Vec<Vec<F>> {
+    (0..1u64 << bit_size)
+        .flat_map(|a| (0..1u64 << bit_size).map(move |b| vec![a.into(), b.into(), (a ^ b).into()]))
+        .collect()
+}
 
-        let t0 = start_timer!(|| format!("{desc} prover"));
-        let proof = create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
-            &params,
-            &pk,
-            &[circuit],
-            &[&[]],
-            OsRng,
-            &mut transcript,
+impl Circuit<Fr> for XorCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = (usize, usize);
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        (bit_size, width): Self::Params,
+    ) -> Self::Config {
+        LogupGate::configure_tuple(meta, xor_table(bit_size), width)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let table_size = 1 << self.bit_size;
+        for _ in 0..self.lookups_per_column {
+            let w = (0..self.width)
+                .map(|_| {
+                    let a = OsRng.gen_range(0..table_size as u64);
+                    let b = OsRng.gen_range(0..table_size as u64);
+                    [a, b, a ^ b]
+                        .into_iter()
+                        .map(|v| Value::known(Fr::from(v)))
+                        .collect()
+                })
+                .collect::<Vec<Vec<Value<Fr>>>>();
+            gate.lookup_tuple(&w);
+        }
+        gate.layout_tuple(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        (self.bit_size, self.width)
+    }
+}
+
+#[test]
+fn test_tuple_lookup() {
+    let circuit = XorCircuit {
+        bit_size: 4,
+        width: 3,
+        lookups_per_column: 1 << 6,
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[derive(Clone, Debug, Default)]
+struct DynamicTableCircuit {
+    table: Vec<Vec<Value<Fr>>>,
+    width: usize,
+    witnesses: Vec<Vec<Vec<Value<Fr>>>>,
+    // table cells returned by the gate are constrained to the wrong rows
+    tamper: bool,
+}
+
+impl DynamicTableCircuit {
+    fn new(width: usize, table_size: usize, lookups_per_column: usize, tamper: bool) -> Self {
+        // random key-value pairs
+        let table = (0..table_size)
+            .map(|_| vec![Fr::random(OsRng), Fr::random(OsRng)])
+            .collect::<Vec<_>>();
+        let witnesses = (0..lookups_per_column)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        let row = &table[OsRng.gen_range(0..table_size)];
+                        row.iter().map(|v| Value::known(*v)).collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let table = table
+            .iter()
+            .map(|row| row.iter().map(|v| Value::known(*v)).collect())
+            .collect();
+        Self {
+            table,
+            width,
+            witnesses,
+            tamper,
+        }
+    }
+}
+
+impl Circuit<Fr> for DynamicTableCircuit {
+    type Config = (LogupGate<Fr>, Column<Advice>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        let unknown = |row: &Vec<Value<Fr>>| vec![Value::unknown(); row.len()];
+        Self {
+            table: self.table.iter().map(unknown).collect(),
+            width: self.width,
+            witnesses: self
+                .witnesses
+                .iter()
+                .map(|w| w.iter().map(unknown).collect())
+                .collect(),
+            tamper: self.tamper,
+        }
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<Fr>, width: usize) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (LogupGate::configure_dynamic(meta, 2, width), a)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let (mut gate, a) = cfg;
+
+        // table of key-value rows is assigned somewhere else in the circuit
+        let cells = assign_column(&mut ly, a, &self.table.concat())?;
+        let table = cells.chunks(2).map(|row| row.to_vec()).collect::<Vec<_>>();
+
+        gate.assign_table(
+            table
+                .iter()
+                .map(|row| row.iter().cloned().map(Witness::Assigned).collect())
+                .collect(),
         );
-        end_timer!(t0);
-        proof.expect("proof generation should not fail");
+        self.witnesses.iter().for_each(|w| gate.lookup_tuple(w));
+        let (_, table_cells) = gate.layout_with_table(&mut ly)?;
+        assert_eq!(table_cells.len(), table.len());
+
+        // cells returned by the gate are the cells of the table
+        let mut expected = table.clone();
+        expected.rotate_left(self.tamper as usize);
+        expect_equal(&mut ly, &table_cells.concat(), &expected.concat())
     }
 
-    #[test]
-    fn bench_prover() {
-        run_bench_prover::<SubsetGate<Fr, 1>, 1>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 2>, 2>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 3>, 3>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 4>, 4>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 5>, 5>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 6>, 6>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 7>, 7>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 8>, 8>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 9>, 9>("subset", 17, 16, 1 << 15);
-        run_bench_prover::<SubsetGate<Fr, 10>, 10>("subset", 17, 16, 1 << 15);
-
-        run_bench_prover::<LogupGate<Fr, 1>, 1>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 2>, 2>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 3>, 3>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 4>, 4>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 5>, 5>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 6>, 6>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 7>, 7>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 8>, 8>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 9>, 9>("logup", 17, 16, 1 << 15);
-        run_bench_prover::<LogupGate<Fr, 10>, 10>("logup", 17, 16, 1 << 15);
+    fn params(&self) -> Self::Params {
+        self.width
+    }
+}
+
+#[test]
+fn test_dynamic_table() {
+    let circuit = DynamicTableCircuit::new(4, 100, 1 << 6, false);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    let circuit = DynamicTableCircuit::new(4, 100, 1 << 6, true);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[derive(Clone, Debug, Default)]
+struct ConditionalCircuit {
+    bit_size: usize,
+    width: usize,
+    lookups_per_column: usize,
+    // first lookup is enabled with an out of table value
+    out_of_table: bool,
+    // first lookup is enabled with a flag of two
+    non_boolean: bool,
+}
+
+impl Circuit<Fr> for ConditionalCircuit {
+    type Config = (LogupGate<Fr>, Column<Advice>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = (usize, usize);
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        (bit_size, width): Self::Params,
+    ) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (LogupGate::configure(meta, range_table(bit_size), width), a)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let (mut gate, a) = cfg;
+        let table_size = 1u64 << self.bit_size;
+        let lookups = (0..self.lookups_per_column)
+            .map(|i| {
+                let mut enable = OsRng.gen_range(0..2u64);
+                if i == 0 && (self.out_of_table || self.non_boolean) {
+                    enable = 1 + self.non_boolean as u64;
+                }
+                let w = (0..self.width)
+                    .map(|_| {
+                        let w = match enable == 0 || (i == 0 && self.out_of_table) {
+                            // disabled lookups are free to be out of the table
+                            true => OsRng.gen_range(table_size..table_size * 2),
+                            false => OsRng.gen_range(0..table_size),
+                        };
+                        Value::known(Fr::from(w))
+                    })
+                    .collect::<Vec<_>>();
+                (w, enable)
+            })
+            .collect::<Vec<_>>();
+
+        // flags are computed somewhere else in the circuit
+        let flags = lookups
+            .iter()
+            .map(|(_, enable)| Value::known(Fr::from(*enable)))
+            .collect::<Vec<_>>();
+        let flags = assign_column(&mut ly, a, &flags)?;
+
+        for ((w, _), enable) in lookups.iter().zip(flags.iter()) {
+            gate.lookup_conditional(w, enable);
+        }
+        let result = gate.layout(&mut ly);
+        if self.non_boolean && cfg!(feature = "prover-sanity") {
+            // flag of two adds the lookup twice to the grand sum
+            assert!(matches!(
+                result,
+                Err(LookupError::Identity(Identity::GrandSum))
+            ));
+        }
+        result?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        (self.bit_size, self.width)
+    }
+}
+
+#[test]
+fn test_conditional_lookup() {
+    let circuit = ConditionalCircuit {
+        bit_size: 5,
+        width: 4,
+        lookups_per_column: 1 << 6,
+        ..Default::default()
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    for (out_of_table, non_boolean) in [(true, false), (false, true)] {
+        let circuit = ConditionalCircuit {
+            bit_size: 5,
+            width: 4,
+            lookups_per_column: 1 << 6,
+            out_of_table,
+            non_boolean,
+        };
+        // may already be caught during synthesis by sanity checks
+        let satisfied = match MockProver::run(10, &circuit, vec![]) {
+            Ok(prover) => prover.verify().is_ok(),
+            Err(_) => false,
+        };
+        assert!(!satisfied);
+    }
+}
+
+#[cfg(feature = "prover-sanity")]
+#[test]
+fn test_identities() {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let mut gate = LogupGate::configure(&mut meta, range_table(4), 2);
+    let alpha = Value::known(Fr::random(OsRng));
+    let beta = Value::known(Fr::random(OsRng));
+    let helpers = |gate: &LogupGate<Fr>| gate.helpers(&gate.padded_table(), alpha, beta);
+
+    gate.lookup(&[Value::known(Fr::from(3u64)), Value::known(Fr::from(5u64))]);
+    let (m, t_helper, w_inv) = helpers(&gate);
+    assert!(LogupGate::check_identities(&m, &t_helper, &w_inv).is_ok());
+
+    // lookups that are not in the table are only caught by the grand sum
+    gate.lookup(&[Value::known(Fr::from(3u64)), Value::known(Fr::from(16u64))]);
+    let (m, t_helper, w_inv) = helpers(&gate);
+    assert!(matches!(
+        LogupGate::check_identities(&m, &t_helper, &w_inv),
+        Err(LookupError::Identity(Identity::GrandSum))
+    ));
+}
+
+#[derive(Clone, Debug, Default)]
+struct GroupedCircuit {
+    bit_size: usize,
+    width: usize,
+    group_size: usize,
+    lookups_per_column: usize,
+}
+
+impl Circuit<Fr> for GroupedCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = (usize, usize, usize);
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        (bit_size, width, group_size): Self::Params,
+    ) -> Self::Config {
+        let table = range_table(bit_size).into_iter().map(|t| vec![t]).collect();
+        LogupGate::configure_grouped(meta, table, width, group_size)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let table_size = 1u64 << self.bit_size;
+        for _ in 0..self.lookups_per_column {
+            let w = (0..self.width)
+                .map(|_| Value::known(Fr::from(OsRng.gen_range(0..table_size))))
+                .collect::<Vec<_>>();
+            gate.lookup(&w);
+        }
+        gate.layout(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        (self.bit_size, self.width, self.group_size)
+    }
+}
+
+#[test]
+fn test_grouped_helpers() {
+    for group_size in [1, 2, 3, 5] {
+        let circuit = GroupedCircuit {
+            bit_size: 5,
+            width: 5,
+            group_size,
+            lookups_per_column: 1 << 6,
+        };
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+fn tagged_tables<F: PrimeField>() -> Vec<Vec<Vec<F>>> {
+    let range = |bit_size| range_table(bit_size).into_iter().map(|t| vec![t]).collect();
+    vec![range(3), xor_table(2), range(5)]
+}
+
+#[derive(Clone, Debug, Default)]
+struct TaggedCircuit {
+    width: usize,
+    lookups_per_column: usize,
+}
+
+impl Circuit<Fr> for TaggedCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<Fr>, width: usize) -> Self::Config {
+        LogupGate::configure_tagged(meta, tagged_tables(), width)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let tables = tagged_tables::<Fr>();
+        for _ in 0..self.lookups_per_column {
+            // every row looks up into a random table
+            let tag = OsRng.gen_range(0..tables.len());
+            let w = (0..self.width)
+                .map(|_| {
+                    let row = &tables[tag][OsRng.gen_range(0..tables[tag].len())];
+                    row.iter().map(|v| Value::known(*v)).collect()
+                })
+                .collect::<Vec<Vec<Value<Fr>>>>();
+            gate.lookup_tagged(tag, &w);
+        }
+        gate.layout_tuple(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        self.width
+    }
+}
+
+#[test]
+fn test_tagged_lookup() {
+    let circuit = TaggedCircuit {
+        width: 3,
+        lookups_per_column: 1 << 6,
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[derive(Clone, Debug, Default)]
+struct ShuffleCircuit {
+    width: usize,
+    inputs: Vec<Vec<Fr>>,
+    outputs: Vec<Vec<Fr>>,
+}
+
+impl ShuffleCircuit {
+    /// Outputs are shuffled rows of inputs. If `mismatch` a value is changed and if `swap` two
+    /// values of a row are swapped so that the multiset of values stays the same.
+    fn new(width: usize, rows: usize, mismatch: bool, swap: bool) -> Self {
+        let inputs = (0..rows)
+            .map(|_| (0..width).map(|_| Fr::random(OsRng)).collect())
+            .collect::<Vec<Vec<Fr>>>();
+        let mut outputs = inputs.clone();
+        outputs.shuffle(&mut OsRng);
+        if mismatch {
+            outputs[0][0] += Fr::ONE;
+        }
+        if swap {
+            outputs[0].swap(0, 1);
+        }
+        Self {
+            width,
+            inputs,
+            outputs,
+        }
+    }
+}
+
+impl Circuit<Fr> for ShuffleCircuit {
+    type Config = ShuffleGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<Fr>, width: usize) -> Self::Config {
+        ShuffleGate::configure(meta, width)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let known = |row: &Vec<Fr>| row.iter().copied().map(Value::known).collect::<Vec<_>>();
+        self.inputs.iter().for_each(|row| gate.input(&known(row)));
+        self.outputs.iter().for_each(|row| gate.output(&known(row)));
+        gate.layout(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        self.width
+    }
+}
+
+#[test]
+fn test_shuffle() {
+    let circuit = ShuffleCircuit::new(3, 1 << 6, false, false);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    let circuit = ShuffleCircuit::new(3, 1 << 6, true, false);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // rows are shuffled, not values
+    let circuit = ShuffleCircuit::new(3, 1 << 6, false, true);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[derive(Clone, Debug, Default)]
+struct EqualityCircuit<Gate: EqualityGate<Fr>> {
+    _marker: PhantomData<Gate>,
+    width: usize,
+    rows: Vec<Vec<Fr>>,
+    equalities: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl<Gate: EqualityGate<Fr>> EqualityCircuit<Gate> {
+    /// Random small values so that many cells are equal, `mismatch` adds an equality between
+    /// two different values
+    fn new(width: usize, n_rows: usize, n_equalities: usize, mismatch: bool) -> Self {
+        let rows = (0..n_rows)
+            .map(|_| {
+                (0..width)
+                    .map(|_| Fr::from(OsRng.gen_range(0..16u64)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<Fr>>>();
+        let cell = || (OsRng.gen_range(0..n_rows), OsRng.gen_range(0..width));
+        let value = |(row, column): (usize, usize)| rows[row][column];
+        let mut equalities = Vec::with_capacity(n_equalities);
+        while equalities.len() < n_equalities {
+            let (cell_0, cell_1) = (cell(), cell());
+            if value(cell_0) == value(cell_1) {
+                equalities.push((cell_0, cell_1));
+            }
+        }
+        if mismatch {
+            loop {
+                let (cell_0, cell_1) = (cell(), cell());
+                if value(cell_0) != value(cell_1) {
+                    equalities.push((cell_0, cell_1));
+                    break;
+                }
+            }
+        }
+        Self {
+            _marker: PhantomData,
+            width,
+            rows,
+            equalities,
+        }
+    }
+}
+
+impl<Gate: EqualityGate<Fr>> Circuit<Fr> for EqualityCircuit<Gate> {
+    type Config = (Gate, Column<Advice>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<Fr>, width: usize) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (Gate::configure(meta, width), a)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let (mut gate, a) = cfg;
+
+        // first row is assigned somewhere else in the circuit and copied into the gate
+        let first = self.rows[0]
+            .iter()
+            .copied()
+            .map(Value::known)
+            .collect::<Vec<_>>();
+        let first = assign_column(&mut ly, a, &first)?;
+        gate.assign(&first.into_iter().map(Witness::Assigned).collect::<Vec<_>>());
+
+        for row in self.rows.iter().skip(1) {
+            let row = row
+                .iter()
+                .map(|v| Witness::Value(Value::known(*v)))
+                .collect::<Vec<_>>();
+            gate.assign(&row);
+        }
+        for (cell_0, cell_1) in self.equalities.iter() {
+            gate.equal(*cell_0, *cell_1);
+        }
+        gate.layout(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        self.width
+    }
+}
+
+#[test]
+fn test_equality() {
+    fn run<Gate: EqualityGate<Fr>>() {
+        let circuit = EqualityCircuit::<Gate>::new(4, 1 << 6, 1 << 7, false);
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = EqualityCircuit::<Gate>::new(4, 1 << 6, 1 << 7, true);
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    run::<PermutationGate<Fr>>();
+    run::<CopyGate<Fr>>();
+}
+
+#[derive(Clone, Debug, Default)]
+struct SplitCircuit {
+    k: u32,
+    bit_size: usize,
+    lookups_per_column: usize,
+}
+
+impl Circuit<Fr> for SplitCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = (u32, usize);
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        (k, bit_size): Self::Params,
+    ) -> Self::Config {
+        let table = range_table(bit_size).into_iter().map(|t| vec![t]).collect();
+        let sets = LogupGate::<Fr>::table_sets_for_k(1 << bit_size, k);
+        LogupGate::configure_split(meta, table, 2, sets)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        gate.set_k(self.k);
+        let table_size = 1u64 << self.bit_size;
+        for _ in 0..self.lookups_per_column {
+            let w: [Value<Fr>; 2] =
+                std::array::from_fn(|_| Value::known(Fr::from(OsRng.gen_range(0..table_size))));
+            gate.lookup(&w);
+        }
+        gate.layout(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        (self.k, self.bit_size)
+    }
+}
+
+#[test]
+fn test_split_table() {
+    // 2^10 table in 2^7 rows
+    let circuit = SplitCircuit {
+        k: 7,
+        bit_size: 10,
+        lookups_per_column: 100,
+    };
+    let prover = MockProver::run(7, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[derive(Clone, Debug, Default)]
+struct ChunkedCircuit {
+    chunk_rows: usize,
+    lookups_per_column: usize,
+}
+
+impl Circuit<Fr> for ChunkedCircuit {
+    type Config = LogupGate<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        LogupGate::configure(meta, range_table(6), 2)
+    }
+
+    fn synthesize(&self, mut gate: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        gate.set_chunk_rows(self.chunk_rows);
+        for _ in 0..self.lookups_per_column {
+            let w: [Value<Fr>; 2] =
+                std::array::from_fn(|_| Value::known(Fr::from(OsRng.gen_range(0..1u64 << 6))));
+            gate.lookup(&w);
+        }
+        let assigned = gate.layout(&mut ly)?;
+        assert_eq!(assigned.len(), self.lookups_per_column);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_chunked_accumulator() {
+    for chunk_rows in [1, 7, 50, 200] {
+        let circuit = ChunkedCircuit {
+            chunk_rows,
+            lookups_per_column: 100,
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+/// Two logup gates over a table that is built once by the caller
+#[derive(Clone, Debug)]
+struct SharedTableCircuit {
+    table: LogupTable<Fr>,
+    lookups_per_column: usize,
+}
+
+impl Circuit<Fr> for SharedTableCircuit {
+    type Config = (LogupGate<Fr>, LogupGate<Fr>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = LogupTable<Fr>;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<Fr>, table: Self::Params) -> Self::Config {
+        let grouped = LogupGate::configure_with_table(meta, &table, 4, 2, 1);
+        let split = LogupGate::configure_with_table(meta, &table, 1, 1, 2);
+        (grouped, split)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!()
+    }
+
+    fn synthesize(
+        &self,
+        (mut grouped, mut split): Self::Config,
+        mut ly: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let table_size = self.table.len() as u64;
+        let w = || Value::known(Fr::from(OsRng.gen_range(0..table_size)));
+        for _ in 0..self.lookups_per_column {
+            grouped.lookup(&[w(), w(), w(), w()]);
+            split.lookup(&[w()]);
+        }
+        grouped.layout(&mut ly)?;
+        split.layout(&mut ly)?;
+        Ok(())
+    }
+
+    fn params(&self) -> Self::Params {
+        self.table.clone()
+    }
+}
+
+#[test]
+fn test_shared_table() {
+    let table = LogupTable::from_values(range_table(8));
+    for lookups_per_column in [10, 100] {
+        let circuit = SharedTableCircuit {
+            table: table.clone(),
+            lookups_per_column,
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+/// Range checks each value to its bit size with 8 bit limbs
+#[derive(Clone, Debug)]
+struct RangeCircuit<Gate> {
+    values: Vec<(Fr, usize)>,
+    _marker: PhantomData<Gate>,
+}
+
+impl<Gate: LookupGate<Fr>> Circuit<Fr> for RangeCircuit<Gate> {
+    type Config = (Column<Advice>, RangeChip<Fr, Gate>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (a, RangeChip::configure(meta, 8, 3))
+    }
+
+    fn synthesize(
+        &self,
+        (a, mut chip): Self::Config,
+        mut ly: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let values = self
+            .values
+            .iter()
+            .map(|(value, _)| Value::known(*value))
+            .collect::<Vec<_>>();
+        let cells = assign_column(&mut ly, a, &values)?;
+        for (cell, (_, bits)) in cells.iter().zip(self.values.iter()) {
+            chip.range_check(&mut ly, cell, *bits)?;
+        }
+        chip.layout(&mut ly)?;
+        Ok(())
+    }
+}
+
+fn run_test_range<Gate: LookupGate<Fr>>() {
+    let random = |bits: usize| {
+        (0..bits).fold(Fr::ZERO, |acc, _| {
+            acc.double() + Fr::from(OsRng.gen_range(0..2u64))
+        })
+    };
+    let pow = |bits: usize| Fr::from(2u64).pow_vartime([bits as u64]);
+    let satisfied = |values: Vec<(Fr, usize)>| {
+        let circuit = RangeCircuit::<Gate> {
+            values,
+            _marker: PhantomData,
+        };
+        // out of range limbs may already be caught during synthesis by sanity checks
+        match MockProver::run(9, &circuit, vec![]) {
+            Ok(prover) => prover.verify().is_ok(),
+            Err(_) => false,
+        }
+    };
+
+    let values = [1, 7, 8, 13, 16, 64, 253]
+        .into_iter()
+        .flat_map(|bits| [(random(bits), bits), (pow(bits) - Fr::ONE, bits)])
+        .collect::<Vec<_>>();
+    assert!(satisfied(values));
+
+    // partial top limb, full top limb and the widest check
+    for bits in [13, 16, 253] {
+        assert!(!satisfied(vec![(pow(bits), bits)]));
+    }
+}
+
+#[test]
+fn test_range_chip() {
+    run_test_range::<LogupGate<Fr>>();
+    run_test_range::<SubsetGate<Fr>>();
+}
+
+/// Checks `lo <= value < hi` for each `(value, lo, hi)` with 8 bit limbs
+#[derive(Clone, Debug)]
+struct BoundedCircuit<Gate> {
+    values: Vec<(Fr, Fr, Fr)>,
+    _marker: PhantomData<Gate>,
+}
+
+impl<Gate: LookupGate<Fr>> Circuit<Fr> for BoundedCircuit<Gate> {
+    type Config = (Column<Advice>, RangeChip<Fr, Gate>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (a, RangeChip::configure(meta, 8, 2))
+    }
+
+    fn synthesize(
+        &self,
+        (a, mut chip): Self::Config,
+        mut ly: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        for (value, lo, hi) in self.values.iter() {
+            let cells = assign_column(&mut ly, a, &[Value::known(*value)])?;
+            chip.range_check_bounded(&mut ly, &cells[0], *lo, *hi)?;
+        }
+        chip.layout(&mut ly)?;
+        Ok(())
+    }
+}
+
+fn run_test_bounded<Gate: LookupGate<Fr>>() {
+    let satisfied = |values: &[(u64, u64, u64)]| {
+        let circuit = BoundedCircuit::<Gate> {
+            values: values
+                .iter()
+                .map(|(value, lo, hi)| (Fr::from(*value), Fr::from(*lo), Fr::from(*hi)))
+                .collect(),
+            _marker: PhantomData,
+        };
+        // out of range limbs may already be caught during synthesis by sanity checks
+        match MockProver::run(9, &circuit, vec![]) {
+            Ok(prover) => prover.verify().is_ok(),
+            Err(_) => false,
+        }
+    };
+
+    let big = 1u64 << 40;
+    assert!(satisfied(&[
+        (0, 0, 1000),
+        (999, 0, 1000),
+        (17, 17, 18),
+        (big, big, big + 300),
+        (big + 299, big, big + 300),
+        (u64::MAX - 1, 5, u64::MAX),
+    ]));
+
+    for (value, lo, hi) in [
+        (1000, 0, 1000),
+        (18, 17, 18),
+        (16, 17, 18),
+        (big - 1, big, big + 300),
+        (big + 300, big, big + 300),
+    ] {
+        assert!(!satisfied(&[(value, lo, hi)]));
+    }
+}
+
+#[test]
+fn test_bounded_range() {
+    run_test_bounded::<LogupGate<Fr>>();
+    run_test_bounded::<SubsetGate<Fr>>();
+}
+
+/// Compares pairs of 32 bit words and constrains results to the expected ones
+#[derive(Clone, Debug, Default)]
+struct ComparisonCircuit {
+    pairs: Vec<(u64, u64)>,
+    tamper: bool,
+}
+
+impl Circuit<Fr> for ComparisonCircuit {
+    type Config = (Column<Advice>, ComparisonChip<Fr, LogupGate<Fr>>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (a, ComparisonChip::configure(meta, 8, 4))
+    }
+
+    fn synthesize(
+        &self,
+        (column, mut chip): Self::Config,
+        mut ly: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        for (i, (a, b)) in self.pairs.iter().enumerate() {
+            // a, b and expected a < b, a >= b, a > b, a <= b
+            let mut values = [*a, *b, (a < b) as u64, (a >= b) as u64]
+                .into_iter()
+                .chain([(a > b) as u64, (a <= b) as u64])
+                .map(Fr::from)
+                .collect::<Vec<_>>();
+            if self.tamper && i == 0 {
+                values[2] = Fr::ONE - values[2];
+            }
+            let values = values.into_iter().map(Value::known).collect::<Vec<_>>();
+            let cells = assign_column(&mut ly, column, &values)?;
+            let (a, b) = (&cells[0], &cells[1]);
+            chip.range().range_check(&mut ly, a, 32)?;
+            chip.range().range_check(&mut ly, b, 32)?;
+
+            let results = [
+                chip.less_than(&mut ly, a, b, 32)?,
+                chip.greater_or_equal(&mut ly, a, b, 32)?,
+                chip.greater_than(&mut ly, a, b, 32)?,
+                chip.less_or_equal(&mut ly, a, b, 32)?,
+            ];
+            expect_equal(&mut ly, &results, &cells[2..])?;
+        }
+        chip.layout(&mut ly)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_comparison() {
+    let mut pairs = vec![
+        (0, 0),
+        (0, 1),
+        (1, 0),
+        (5, 5),
+        (u32::MAX as u64, 0),
+        (0, u32::MAX as u64),
+    ];
+    pairs.extend((0..6).map(|_| {
+        let a = OsRng.gen_range(0..1u64 << 32);
+        let b = OsRng.gen_range(0..1u64 << 32);
+        (a, b)
+    }));
+
+    let circuit = ComparisonCircuit {
+        pairs: pairs.clone(),
+        tamper: false,
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    let circuit = ComparisonCircuit {
+        pairs,
+        tamper: true,
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Applies bitwise operations to pairs of words with 4 bit chunks and constrains results to
+/// the expected ones
+#[derive(Clone, Debug, Default)]
+struct BitwiseCircuit {
+    words: Vec<(u64, u64, usize)>,
+    tamper: bool,
+}
+
+impl Circuit<Fr> for BitwiseCircuit {
+    type Config = (Column<Advice>, BitwiseChip<Fr>);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        meta.enable_equality(a);
+        (a, BitwiseChip::configure(meta, 4, 2))
+    }
+
+    fn synthesize(
+        &self,
+        (column, mut chip): Self::Config,
+        mut ly: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        for (i, (a, b, bits)) in self.words.iter().enumerate() {
+            let mask = u64::MAX >> (64 - bits);
+            // a, b and expected a ^ b, a & b, a | b, !a
+            let mut values = [*a, *b, a ^ b, a & b, a | b, !a & mask].map(Fr::from);
+            if self.tamper && i == 0 {
+                values[2] += Fr::ONE;
+            }
+            let cells = assign_column(&mut ly, column, &values.map(Value::known))?;
+            let (a, b) = (&cells[0], &cells[1]);
+
+            let results = [
+                chip.xor(&mut ly, a, b, *bits)?,
+                chip.and(&mut ly, a, b, *bits)?,
+                chip.or(&mut ly, a, b, *bits)?,
+                chip.not(&mut ly, a, *bits)?,
+            ];
+            expect_equal(&mut ly, &results, &cells[2..])?;
+        }
+        chip.layout(&mut ly)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_bitwise() {
+    let mut words = vec![
+        (0, 0, 32),
+        (0xdeadbeef, 0x0f0f0f0f, 32),
+        (u32::MAX as u64, 0x12345678, 32),
+        (u64::MAX, 0x0123456789abcdef, 64),
+    ];
+    words.extend((0..3).map(|_| (OsRng.gen::<u32>() as u64, OsRng.gen::<u32>() as u64, 32)));
+    words.extend((0..3).map(|_| (OsRng.gen::<u64>(), OsRng.gen::<u64>(), 64)));
+
+    let circuit = BitwiseCircuit {
+        words: words.clone(),
+        tamper: false,
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    let circuit = BitwiseCircuit {
+        words,
+        tamper: true,
+    };
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Logup gate over a range table with random lookups
+#[cfg(feature = "parallel")]
+fn random_logup_gate(bit_size: usize, width: usize, lookups_per_column: usize) -> LogupGate<Fr> {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let mut gate = LogupGate::configure(&mut meta, range_table(bit_size), width);
+    let mut rng = OsRng;
+    for _ in 0..lookups_per_column {
+        let value = (0..width)
+            .map(|_| Value::known(Fr::from(rng.gen_range(0..1u64 << bit_size))))
+            .collect::<Vec<_>>();
+        gate.lookup(&value);
+    }
+    gate
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_helpers() {
+    let evaluate = |values: &[Value<halo2::plonk::Assigned<Fr>>]| {
+        let values: Value<Vec<Fr>> = values.iter().map(|v| v.evaluate()).collect();
+        let mut evaluated = Vec::new();
+        values.map(|values| evaluated = values);
+        evaluated
+    };
+
+    let gate = random_logup_gate(8, 3, 300);
+    let table = gate.padded_table();
+    let alpha = Value::known(Fr::random(OsRng));
+    let beta = Value::known(Fr::random(OsRng));
+
+    let (m_0, t_helper_0, w_inv_0) = gate.helpers(&table, alpha, beta);
+    let (m_1, t_helper_1, w_inv_1) = gate.helpers_parallel(&table, alpha, beta);
+    assert_eq!(evaluate(&m_0), evaluate(&m_1));
+    assert_eq!(evaluate(&t_helper_0), evaluate(&t_helper_1));
+    assert_eq!(w_inv_0.len(), w_inv_1.len());
+    for (w_inv_0, w_inv_1) in w_inv_0.iter().zip(w_inv_1.iter()) {
+        assert_eq!(evaluate(w_inv_0), evaluate(w_inv_1));
+    }
+}
+
+/// Lays out a single column logup argument over the table `0..8` by hand so that the prover
+/// can forge multiplicities `m` and enable flags `q` of `witnesses`. A multiplicity of `None`
+/// is picked after `alpha` is known so that the grand sum is zero.
+#[derive(Clone, Debug, Default)]
+struct HandLogupCircuit {
+    witnesses: Vec<u64>,
+    m: Vec<Option<u64>>,
+    q: Vec<u64>,
+}
+
+impl HandLogupCircuit {
+    fn honest() -> Self {
+        Self {
+            witnesses: vec![1, 2, 3],
+            m: [0, 1, 1, 1, 0, 0, 0, 0].map(Some).to_vec(),
+            q: vec![1, 1, 1],
+        }
+    }
+
+    /// Covers a value that is not in the table with the multiplicity of `0`,
+    /// `m_0 / (alpha - 0) = 1 / (alpha - 100)`
+    fn forged_multiplicity() -> Self {
+        let mut m = [0, 1, 1, 0, 0, 0, 0, 0].map(Some).to_vec();
+        m[0] = None;
+        Self {
+            witnesses: vec![1, 2, 100],
+            m,
+            q: vec![1, 1, 1],
+        }
+    }
+
+    /// Disables an unconditional lookup of a value that is not in the table with a zero flag
+    fn forged_enable() -> Self {
+        Self {
+            witnesses: vec![1, 2, 100],
+            m: [0, 1, 1, 0, 0, 0, 0, 0].map(Some).to_vec(),
+            q: vec![1, 1, 0],
+        }
+    }
+}
+
+impl Circuit<Fr> for HandLogupCircuit {
+    type Config = LogupConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let w = vec![vec![meta.advice_column()]];
+        let t: Vec<Vec<Column<Any>>> = vec![vec![meta.fixed_column().into()]];
+        LogupConfig::configure(meta, &w, None, &t, 1)
+    }
+
+    fn synthesize(&self, cfg: Self::Config, mut ly: impl Layouter<Fr>) -> Result<(), Error> {
+        let alpha = ly.get_challenge(cfg.alpha);
+        let inv = |value: Fr| alpha.map(|alpha| (alpha - value).invert().unwrap());
+
+        let table = range_table::<Fr>(3);
+        assert!(self.m.len() == table.len() && self.witnesses.len() <= table.len());
+        let witnesses = self
+            .witnesses
+            .iter()
+            .map(|w| Fr::from(*w))
+            .collect::<Vec<_>>();
+        let t_helper = table.iter().map(|t| inv(*t)).collect::<Vec<_>>();
+        let w_helper = witnesses
+            .iter()
+            .zip(self.q.iter())
+            .map(|(w, q)| inv(*w) * Value::known(Fr::from(*q)))
+            .collect::<Vec<_>>();
+
+        // free multiplicity takes whatever is left of the grand sum
+        let zero = Value::known(Fr::ZERO);
+        let rhs = w_helper.iter().fold(zero, |acc, h| acc + *h);
+        let lhs = self
+            .m
+            .iter()
+            .zip(t_helper.iter())
+            .filter_map(|(m, h)| m.map(|m| *h * Value::known(Fr::from(m))))
+            .fold(zero, |acc, h| acc + h);
+        let m = self
+            .m
+            .iter()
+            .zip(table.iter())
+            .map(|(m, t)| match m {
+                Some(m) => Value::known(Fr::from(*m)),
+                None => (rhs - lhs) * alpha.map(|alpha| alpha - t),
+            })
+            .collect::<Vec<_>>();
+
+        ly.assign_region(
+            || "hand",
+            |region| {
+                let mut ctx = RegionCtx::new(region);
+
+                let mut acc = Value::known(Fr::ZERO);
+                ctx.enable(cfg.s_zero)?;
+
+                for (i, t) in table.iter().enumerate() {
+                    ctx.enable(cfg.s_acc)?;
+                    ctx.advice(cfg.acc, acc.into())?;
+
+                    ctx.enable(cfg.s_table)?;
+                    ctx.assign(cfg.t[0][0], Value::known(*t))?;
+                    ctx.advice(cfg.t_helper[0], t_helper[i].into())?;
+                    ctx.advice(cfg.m[0], m[i].into())?;
+                    acc = acc + t_helper[i] * m[i];
+
+                    match witnesses.get(i) {
+                        Some(w) => {
+                            ctx.enable(cfg.s_witness)?;
+                            ctx.advice(cfg.q, Value::known(Fr::from(self.q[i])).into())?;
+                            ctx.advice(cfg.w[0][0], Value::known(*w).into())?;
+                            ctx.advice(cfg.w_helper[0], w_helper[i].into())?;
+                            acc = acc - w_helper[i];
+                        }
+                        None => {
+                            ctx.empty(cfg.q.into())?;
+                            ctx.empty(cfg.w[0][0].into())?;
+                            ctx.empty(cfg.w_helper[0].into())?;
+                        }
+                    }
+                    ctx.next();
+                }
+
+                ctx.advice(cfg.acc, acc.into())?;
+                ctx.enable(cfg.s_zero)?;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_forged_enable() {
+    let prover = MockProver::run(6, &HandLogupCircuit::honest(), vec![]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(6, &HandLogupCircuit::forged_enable(), vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+mod prover {
+
+    use std::marker::PhantomData;
+
+    use ark_std::{end_timer, start_timer};
+    #[cfg(feature = "parallel")]
+    use ff::{BatchInvert, Field};
+    #[cfg(feature = "parallel")]
+    use halo2::circuit::Value;
+    use halo2::halo2curves::bn256::{Bn256, Fr};
+    use halo2::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error};
+    use halo2::poly::commitment::ParamsProver;
+    use halo2::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+    use halo2::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+    use halo2::poly::kzg::strategy::SingleStrategy;
+    use halo2::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
+    use rand_core::OsRng;
+
+    use crate::copy::assignments::CopyGate;
+    use crate::logup::assignments::LogupGate;
+    use crate::logup::table::LogupTable;
+    use crate::permutation::assignments::PermutationGate;
+    use crate::subset::assignments::SubsetGate;
+    use crate::{range_table, EqualityGate, LookupGate};
+
+    #[cfg(feature = "parallel")]
+    use super::random_logup_gate;
+    use super::{
+        EqualityCircuit, GroupedCircuit, HandLogupCircuit, SharedTableCircuit, SplitCircuit,
+        TestCircuit,
+    };
+
+    fn run_bench_prover<Gate: LookupGate<Fr>>(
+        desc: &str,
+        k: u32,
+        bit_size: usize,
+        width: usize,
+        lookups_per_column: usize,
+    ) {
+        let circuit = TestCircuit::<Fr, Gate> {
+            _marker: PhantomData,
+            table: range_table(bit_size),
+            width,
+            lookups_per_column,
+        };
+
+        let desc = format!(
+            "{desc}, k: {k}, W: {width}, b: {bit_size}, l: {lookups_per_column}, n: {}",
+            lookups_per_column * width
+        );
+        bench_circuit(&desc, k, circuit);
+    }
+
+    fn run_bench_grouped(
+        desc: &str,
+        k: u32,
+        bit_size: usize,
+        width: usize,
+        group_size: usize,
+        lookups_per_column: usize,
+    ) {
+        let circuit = GroupedCircuit {
+            bit_size,
+            width,
+            group_size,
+            lookups_per_column,
+        };
+
+        let desc = format!(
+            "{desc}, k: {k}, W: {width}, g: {group_size}, b: {bit_size}, l: {lookups_per_column}, n: {}",
+            lookups_per_column * width
+        );
+        bench_circuit(&desc, k, circuit);
+    }
+
+    fn run_bench_equality<Gate: EqualityGate<Fr>>(
+        desc: &str,
+        k: u32,
+        width: usize,
+        n_rows: usize,
+        n_equalities: usize,
+    ) {
+        let circuit = EqualityCircuit::<Gate>::new(width, n_rows, n_equalities, false);
+        let desc = format!("{desc}, k: {k}, W: {width}, rows: {n_rows}, e: {n_equalities}");
+        bench_circuit(&desc, k, circuit);
+    }
+
+    #[cfg(feature = "parallel")]
+    fn run_bench_helpers(bit_size: usize, width: usize, lookups_per_column: usize) {
+        let gate = random_logup_gate(bit_size, width, lookups_per_column);
+        let table = gate.padded_table();
+        let alpha = Value::known(Fr::random(OsRng));
+        let beta = Value::known(Fr::random(OsRng));
+
+        let desc = format!("W: {width}, b: {bit_size}, l: {lookups_per_column}");
+        let t0 = start_timer!(|| format!("{desc} serial helpers"));
+        let helpers = gate.helpers(&table, alpha, beta);
+        // halo2 inverts the rationals when cells are assigned
+        let denominators: Value<Vec<Fr>> = helpers
+            .0
+            .iter()
+            .chain(helpers.1.iter())
+            .chain(helpers.2.iter().flatten())
+            .map(|h| h.map(|h| h.denominator().unwrap_or(Fr::ONE)))
+            .collect();
+        denominators.map(|mut denominators| denominators.iter_mut().batch_invert());
+        end_timer!(t0);
+
+        let t0 = start_timer!(|| format!("{desc} parallel helpers"));
+        gate.helpers_parallel(&table, alpha, beta);
+        end_timer!(t0);
+    }
+
+    fn bench_circuit<C: Circuit<Fr>>(desc: &str, k: u32, circuit: C) {
+        let params = read_srs(k);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+
+        let t0 = start_timer!(|| format!("{desc} prover"));
+        let proof = create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[]],
+            OsRng,
+            &mut transcript,
+        );
+        end_timer!(t0);
+        proof.expect("proof generation should not fail");
+    }
+
+    fn prove_and_verify<C: Circuit<Fr>>(k: u32, circuit: C) -> Result<(), Error> {
+        let params = ParamsKZG::<Bn256>::new(k);
+        let vk = keygen_vk(&params, &circuit)?;
+        let pk = keygen_pk(&params, vk, &circuit)?;
+
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[]],
+            OsRng,
+            &mut transcript,
+        )?;
+        let proof = transcript.finalize();
+
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<Bn256>, _, _, _>(
+            &params,
+            pk.get_vk(),
+            SingleStrategy::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+    }
+
+    #[test]
+    fn test_shared_table_proofs() {
+        // table is built once and reused by keygen and every proof
+        let table = LogupTable::from_values(range_table(8));
+        for lookups_per_column in [10, 100] {
+            let circuit = SharedTableCircuit {
+                table: table.clone(),
+                lookups_per_column,
+            };
+            prove_and_verify(9, circuit).expect("proof with a shared table should verify");
+        }
+    }
+
+    #[test]
+    fn test_forged_multiplicities() {
+        prove_and_verify(5, HandLogupCircuit::honest())
+            .expect("honest multiplicities should be accepted");
+        assert!(prove_and_verify(5, HandLogupCircuit::forged_multiplicity()).is_err());
+    }
+
+    #[test]
+    fn bench_prover() {
+        for width in 1..=10 {
+            run_bench_prover::<SubsetGate<Fr>>("subset", 17, 16, width, 1 << 15);
+        }
+        for width in 1..=10 {
+            run_bench_prover::<LogupGate<Fr>>("logup", 17, 16, width, 1 << 15);
+        }
+        // fewer witness helper columns for a higher degree
+        for group_size in [2, 3, 5, 10] {
+            run_bench_grouped("logup grouped", 17, 16, 10, group_size, 1 << 15);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_prover_split() {
+        // 16 bit range checks in a small circuit
+        let circuit = SplitCircuit {
+            k: 14,
+            bit_size: 16,
+            lookups_per_column: 1 << 13,
+        };
+        bench_circuit("logup split, k: 14, W: 2, b: 16, l: 8192", 14, circuit);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    #[ignore]
+    fn bench_helpers() {
+        for width in [1, 4, 10] {
+            run_bench_helpers(16, width, 1 << 16);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_equality() {
+        for width in [1, 4, 8] {
+            run_bench_equality::<PermutationGate<Fr>>("permutation", 17, width, 1 << 16, 1 << 16);
+            run_bench_equality::<CopyGate<Fr>>("copy", 17, width, 1 << 16, 1 << 16);
+        }
     }
 
     fn write_srs(k: u32) -> ParamsKZG<Bn256> {